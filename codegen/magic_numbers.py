@@ -0,0 +1,94 @@
+import random
+
+
+# NOTE: square index is file + rank * 8, so a1 = 0 and h8 = 63
+ROOK_DIRECTIONS = [(0, 1), (0, -1), (1, 0), (-1, 0)]
+BISHOP_DIRECTIONS = [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+
+FULL = 2**64 - 1
+
+
+def sliding_attacks(square, occupied, directions):
+    result = 0
+
+    for dx, dy in directions:
+        x, y = square % 8 + dx, square // 8 + dy
+        while 0 <= x < 8 and 0 <= y < 8:
+            result |= 1 << (x + y * 8)
+            if occupied & (1 << (x + y * 8)):
+                break
+
+            x, y = x + dx, y + dy
+
+    return result
+
+
+# Squares where blockers change attacks (board edges are excluded)
+def relevant_mask(square, directions):
+    result = 0
+
+    for dx, dy in directions:
+        x, y = square % 8 + dx, square // 8 + dy
+        while 0 <= x + dx < 8 and 0 <= y + dy < 8:
+            result |= 1 << (x + y * 8)
+            x, y = x + dx, y + dy
+
+    return result
+
+
+def subsets(mask):
+    subset = 0
+    while True:
+        yield subset
+
+        subset = (subset - mask) & mask
+        if subset == 0:
+            break
+
+
+def gen_sparse_rand():
+    return random.getrandbits(64) & random.getrandbits(64) & random.getrandbits(64)
+
+
+def find_magic(square, directions):
+    mask = relevant_mask(square, directions)
+    bits = mask.bit_count()
+    shift = 64 - bits
+
+    occupancies = list(subsets(mask))
+    attacks = [sliding_attacks(square, o, directions) for o in occupancies]
+
+    while True:
+        magic = gen_sparse_rand()
+
+        # Too few bits in high byte, not good for index
+        if ((mask * magic) & FULL) >> 56 == 0:
+            continue
+
+        table = {}
+        for occupancy, attack in zip(occupancies, attacks):
+            index = ((occupancy * magic) & FULL) >> shift
+            if table.setdefault(index, attack) != attack:
+                break
+        else:
+            return magic
+
+
+def print_table(name, directions):
+    values = [
+        hex(find_magic(square, directions))
+        for square in range(64)
+    ]
+
+    print(f"const {name}: [u64; 64] = [")
+    for rank in range(8):
+        row = ", ".join(values[rank * 8:(rank + 1) * 8])
+        print(f"    {row},")
+    print("];")
+
+
+random.seed(0x6D61726375730000)
+
+print_table("ROOK_MAGICS", ROOK_DIRECTIONS)
+print()
+print_table("BISHOP_MAGICS", BISHOP_DIRECTIONS)
@@ -0,0 +1,550 @@
+use std::sync::OnceLock;
+
+use crate::prelude::*;
+
+const KNIGHT_DIRECTIONS: [(i8, i8); 8] = [
+    (-2, -1),
+    (-1, -2),
+    (-2,  1),
+    (-1,  2),
+    ( 2, -1),
+    ( 1, -2),
+    ( 2,  1),
+    ( 1,  2),
+];
+
+const KING_DIRECTIONS: [(i8, i8); 8] = [
+    (-1, -1),
+    (-1,  0),
+    (-1,  1),
+    ( 0, -1),
+    ( 0,  1),
+    ( 1, -1),
+    ( 1,  0),
+    ( 1,  1),
+];
+
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [
+    (-1, -1),
+    (-1,  1),
+    ( 1, -1),
+    ( 1,  1),
+];
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [
+    ( 0,  1),
+    ( 0, -1),
+    ( 1,  0),
+    (-1,  0),
+];
+
+// NOTE: generated with codegen/magic_numbers.py
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080002210844000, 0x40081002c02000, 0x180100198802000, 0x80100008008004, 0x80020c00808800, 0x68001800c000200, 0x14000d12300400a8, 0x1000021000a8842,
+    0x1002900408000, 0x420804000200b82, 0x2002001022014080, 0x8801000080280, 0x800800040081, 0x584c802201040080, 0x400042a305108, 0x2022800061000080,
+    0x804208000401080, 0x840848040002008, 0x410020001101, 0x850808010002804, 0x802020004081020, 0x109080110400460, 0xc200040002081001, 0x108020004412084,
+    0x202088248000c002, 0x8a00040100044, 0x4040200080100080, 0x506001200221840, 0x400080080040180, 0x602006280140080, 0x4000280c00025001, 0x100812e200008401,
+    0x880204000800188, 0x20003008400040, 0x10202001090010c0, 0x2080820800801000, 0x6a82510085000800, 0x200101e000408, 0x20050410c002812, 0x321800040800100,
+    0x2804001208000, 0x4c00500020004000, 0xc0200108410013, 0x8204200a00420010, 0x4000050068010010, 0x80160c4020080110, 0x14181042040011, 0x24440040a20001,
+    0x100800240042880, 0x2420a00240008080, 0x1082000401500, 0x88011000088280, 0x8004440008008080, 0x2001104082200, 0xd02002408010600, 0x5c01108200,
+    0x181401101628003, 0x2010040201082, 0x80400a11002003, 0x8100100440821, 0x2003004486082, 0x40a004124300812, 0x240050210428814, 0x8001002044008312,
+];
+
+const BISHOP_MAGICS: [u64; 64] = [
+    0x908101400604200, 0x2040404025008, 0x10041050400002, 0x288c0c0080030041, 0x1264030886000002, 0x1010420040400c2, 0x2880844402402001, 0x2005002813041017,
+    0x602204310401, 0x828520c00c200, 0x80040404304080, 0x2801940c04804000, 0x4042210040c, 0x880012410411061, 0xd68d110b1802420c, 0x1008008084100304,
+    0x820004024018201, 0x2025030aa120404, 0x420810016600a0, 0x44804049200c001, 0x4000800401a00804, 0x1005001200808482, 0x8004048184040222, 0x5042000522110400,
+    0x4100822427020, 0x200210b120140482, 0x80480c1188046122, 0x104004084010042, 0x1840000802000, 0x80a0007110880, 0x8140848281081815, 0x10e6020000484206,
+    0x2254200801204298, 0x40080130c0040400, 0x4439402000c0801, 0x930a0080080280, 0x820e0400820028, 0x4210020201042280, 0x801022096240422, 0x40c2b40818140,
+    0x2242103008683428, 0x410c050402205008, 0x9102020122044c00, 0x1900982018020302, 0x4000180104000110, 0x12018b000824041, 0x402082114005124, 0x4202060401120420,
+    0x100908410404041, 0x49820801140610, 0xa000120106880008, 0x101242020000, 0x8281202a441000, 0x2800200410108620, 0x2090427001020520, 0x8010675800808005,
+    0x2000508808084400, 0x2040203402121000, 0x3000004104020600, 0x81100400209800, 0x728050028430400, 0x8414810010200, 0x1204040408080120, 0xc0406c0320410100,
+];
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_table(&KNIGHT_DIRECTIONS);
+const KING_ATTACKS: [Bitboard; 64] = leaper_table(&KING_DIRECTIONS);
+
+// Indexed by color of attacking pawn
+const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_table(&[(-1, -1), (1, -1)]),
+    leaper_table(&[(-1, 1), (1, 1)]),
+];
+
+// Squares strictly between two squares on the same line, empty otherwise
+static BETWEEN: [[Bitboard; 64]; 64] = line_table(false);
+
+// Whole line (edge to edge) through two squares, empty if they aren't aligned
+static LINE: [[Bitboard; 64]; 64] = line_table(true);
+
+const fn line_table(whole_line: bool) -> [[Bitboard; 64]; 64] {
+    let mut xs = [[BitboardEmpty; 64]; 64];
+
+    let mut from = 0;
+    while from < 64 {
+        let from_square = Square::from_index(from);
+
+        let mut i = 0;
+        while i < KING_DIRECTIONS.len() {
+            let (dx, dy) = KING_DIRECTIONS[i];
+
+            let line = sliding_attacks(from_square, BitboardEmpty, &[(dx, dy), (-dx, -dy)]).inner() |
+                Bitboard::from_square(from_square).inner();
+
+            let mut between = 0;
+            let mut d = 1;
+            while d < 8 {
+                let to = match from_square.by(dx * d, dy * d) {
+                    Some(s) => s,
+                    None => break,
+                };
+
+                xs[from as usize][to.index() as usize] = if whole_line {
+                    Bitboard::from_inner(line)
+                } else {
+                    Bitboard::from_inner(between)
+                };
+
+                between |= Bitboard::from_square(to).inner();
+                d += 1;
+            }
+
+            i += 1;
+        }
+
+        from += 1;
+    }
+
+    xs
+}
+
+const fn leaper_table(directions: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut xs = [BitboardEmpty; 64];
+
+    let mut square = 0;
+    while square < 64 {
+        xs[square as usize] = sliding_attacks(
+            Square::from_index(square),
+            BitboardFull,
+            directions,
+        );
+
+        square += 1;
+    }
+
+    xs
+}
+
+// Walks rays square by square until the first blocker (inclusive)
+// NOTE: it's slow, so it's used only to fill attack tables
+//       and as a reference implementation
+pub const fn sliding_attacks(
+    square: Square,
+    occupied: Bitboard,
+    directions: &[(i8, i8)],
+) -> Bitboard {
+    let mut result = 0;
+
+    let mut i = 0;
+    while i < directions.len() {
+        let (dx, dy) = directions[i];
+
+        let mut d = 1;
+        while d < 8 {
+            let target = match square.by(dx * d, dy * d) {
+                Some(s) => s,
+                None => break,
+            };
+
+            let target = Bitboard::from_square(target).inner();
+            result |= target;
+
+            if occupied.inner() & target != 0 {
+                break;
+            }
+
+            d += 1;
+        }
+
+        i += 1;
+    }
+
+    Bitboard::from_inner(result)
+}
+
+// Squares which blockers can change attacks from `square`
+// NOTE: last square of each ray doesn't matter, cause it's attacked anyway
+const fn relevant_mask(square: Square, directions: &[(i8, i8)]) -> Bitboard {
+    let mut result = 0;
+
+    let mut i = 0;
+    while i < directions.len() {
+        let (dx, dy) = directions[i];
+
+        let mut d = 1;
+        while d < 8 {
+            if square.by(dx * (d + 1), dy * (d + 1)).is_none() {
+                break;
+            }
+
+            let target = unwrap_unchecked!(square.by(dx * d, dy * d));
+            result |= Bitboard::from_square(target).inner();
+
+            d += 1;
+        }
+
+        i += 1;
+    }
+
+    Bitboard::from_inner(result)
+}
+
+#[cfg(all(feature = "pext", not(target_feature = "bmi2")))]
+compile_error!("`pext` feature requires BMI2, build with RUSTFLAGS=\"-C target-feature=+bmi2\"");
+
+// NOTE: with `pext` feature magic and shift aren't used,
+//       but table layout is the same: 2^(mask bits) entries per square
+#[derive(Clone, Copy)]
+struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    #[cfg(not(feature = "pext"))]
+    #[inline(always)]
+    fn index(&self, occupied: Bitboard) -> usize {
+        let relevant = (occupied & self.mask).inner();
+
+        self.offset + (relevant.wrapping_mul(self.magic) >> self.shift) as usize
+    }
+
+    #[cfg(feature = "pext")]
+    #[inline(always)]
+    fn index(&self, occupied: Bitboard) -> usize {
+        use std::arch::x86_64::_pext_u64;
+
+        // SAFETY: BMI2 availability is checked at compile time
+        let relevant = unsafe { _pext_u64(occupied.inner(), self.mask.inner()) };
+
+        self.offset + relevant as usize
+    }
+}
+
+struct SliderAttacks {
+    bishop_magics: [Magic; 64],
+    rook_magics: [Magic; 64],
+
+    // PERF: try to share entries between squares (like fancy magics do)
+    attacks: Box<[Bitboard]>,
+}
+
+impl SliderAttacks {
+    fn new() -> Self {
+        let mut attacks = Vec::new();
+
+        let bishop_magics = Self::fill(&mut attacks, &BISHOP_MAGICS, &BISHOP_DIRECTIONS);
+        let rook_magics = Self::fill(&mut attacks, &ROOK_MAGICS, &ROOK_DIRECTIONS);
+
+        Self {
+            bishop_magics,
+            rook_magics,
+            attacks: attacks.into_boxed_slice(),
+        }
+    }
+
+    fn fill(
+        attacks: &mut Vec<Bitboard>,
+        magics: &[u64; 64],
+        directions: &[(i8, i8)],
+    ) -> [Magic; 64] {
+        let mut result = [Magic {
+            mask: BitboardEmpty,
+            magic: 0,
+            shift: 0,
+            offset: 0,
+        }; 64];
+
+        for square in Square::iter() {
+            let mask = relevant_mask(square, directions);
+            let bits = mask.count();
+
+            let magic = Magic {
+                mask,
+                magic: get_unchecked!(magics, square.index()),
+                shift: 64 - bits,
+                offset: attacks.len(),
+            };
+
+            attacks.resize(attacks.len() + (1 << bits), BitboardEmpty);
+
+            // NOTE: enumerates all subsets of mask (Carry-Rippler trick)
+            let mut occupied: BitboardInner = 0;
+            loop {
+                let occupied_bitboard = Bitboard::from_inner(occupied);
+                let index = magic.index(occupied_bitboard);
+                let expected = sliding_attacks(square, occupied_bitboard, directions);
+
+                // NOTE: magic numbers must not have destructive collisions
+                always!(attacks[index] == BitboardEmpty || attacks[index] == expected);
+                attacks[index] = expected;
+
+                occupied = occupied.wrapping_sub(mask.inner()) & mask.inner();
+                if occupied == 0 {
+                    break;
+                }
+            }
+
+            set_unchecked!(result, square.index(), magic);
+        }
+
+        result
+    }
+}
+
+static SLIDER_ATTACKS: OnceLock<SliderAttacks> = OnceLock::new();
+
+// NOTE: tables must be filled with `init_attacks` before the first lookup,
+//       so hot path doesn't pay for initialization check
+#[cfg(not(test))]
+#[inline(always)]
+fn slider_attacks() -> &'static SliderAttacks {
+    unwrap_unchecked!(SLIDER_ATTACKS.get())
+}
+
+// NOTE: tests don't go through `main`, so tables are filled on first lookup
+#[cfg(test)]
+#[inline(always)]
+fn slider_attacks() -> &'static SliderAttacks {
+    SLIDER_ATTACKS.get_or_init(SliderAttacks::new)
+}
+
+// Fills slider attack tables, must be called before any slider attacks lookup
+pub fn init_attacks() {
+    SLIDER_ATTACKS.get_or_init(SliderAttacks::new);
+}
+
+#[inline(always)]
+pub fn between(a: Square, b: Square) -> Bitboard {
+    get_unchecked_2d!(BETWEEN, a.index(), b.index())
+}
+
+#[inline(always)]
+pub fn line(a: Square, b: Square) -> Bitboard {
+    get_unchecked_2d!(LINE, a.index(), b.index())
+}
+
+#[inline(always)]
+pub const fn knight_attacks(square: Square) -> Bitboard {
+    get_unchecked!(KNIGHT_ATTACKS, square.index())
+}
+
+#[inline(always)]
+pub const fn king_attacks(square: Square) -> Bitboard {
+    get_unchecked!(KING_ATTACKS, square.index())
+}
+
+// Squares attacked by a pawn of `color` standing on `square`
+#[inline(always)]
+pub const fn pawn_attacks(color: Color, square: Square) -> Bitboard {
+    get_unchecked_2d!(PAWN_ATTACKS, color.index(), square.index())
+}
+
+#[inline(always)]
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let tables = slider_attacks();
+    let magic = get_unchecked!(tables.bishop_magics, square.index());
+
+    get_unchecked!(tables.attacks, magic.index(occupied))
+}
+
+#[inline(always)]
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let tables = slider_attacks();
+    let magic = get_unchecked!(tables.rook_magics, square.index());
+
+    get_unchecked!(tables.attacks, magic.index(occupied))
+}
+
+#[inline(always)]
+pub fn queen_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitboard(squares: &[Square]) -> Bitboard {
+        let mut result = BitboardEmpty;
+        for &square in squares {
+            result.set(square);
+        }
+
+        result
+    }
+
+    #[test]
+    fn knight() {
+        assert_eq!(knight_attacks(a1), bitboard(&[b3, c2]));
+        assert_eq!(knight_attacks(e4).count(), 8);
+        assert_eq!(knight_attacks(h8), bitboard(&[g6, f7]));
+    }
+
+    #[test]
+    fn king() {
+        assert_eq!(king_attacks(a1), bitboard(&[a2, b1, b2]));
+        assert_eq!(king_attacks(e4).count(), 8);
+    }
+
+    #[test]
+    fn pawn() {
+        assert_eq!(pawn_attacks(White, e4), bitboard(&[d5, f5]));
+        assert_eq!(pawn_attacks(Black, e4), bitboard(&[d3, f3]));
+        assert_eq!(pawn_attacks(White, a2), bitboard(&[b3]));
+        assert_eq!(pawn_attacks(Black, h7), bitboard(&[g6]));
+        assert_eq!(pawn_attacks(White, e8), BitboardEmpty);
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(between(a1, d4), bitboard(&[b2, c3]));
+        assert_eq!(between(d4, a1), bitboard(&[b2, c3]));
+        assert_eq!(between(e1, e8), bitboard(&[e2, e3, e4, e5, e6, e7]));
+        assert_eq!(between(e1, e2), BitboardEmpty);
+        assert_eq!(between(a1, b3), BitboardEmpty);
+
+        assert_eq!(line(c3, e5), line(a1, h8));
+        assert_eq!(line(a1, h8).count(), 8);
+        assert_eq!(line(e4, e5), Bitboard::from_file(FileE));
+        assert_eq!(line(a1, b3), BitboardEmpty);
+    }
+
+    #[test]
+    fn relevant_masks() {
+        assert_eq!(relevant_mask(a1, &ROOK_DIRECTIONS).count(), 12);
+        assert_eq!(relevant_mask(e4, &ROOK_DIRECTIONS).count(), 10);
+        assert_eq!(relevant_mask(a1, &BISHOP_DIRECTIONS).count(), 6);
+        assert_eq!(relevant_mask(e4, &BISHOP_DIRECTIONS).count(), 9);
+    }
+
+    #[test]
+    fn bishop() {
+        let occupied = bitboard(&[b6, f6, b2, f5]);
+
+        assert_eq!(
+            bishop_attacks(d4, occupied),
+            bitboard(&[c5, b6, e5, f6, c3, b2, e3, f2, g1]),
+        );
+    }
+
+    #[test]
+    fn rook() {
+        let occupied = bitboard(&[d6, b4, d1, h4]);
+
+        assert_eq!(
+            rook_attacks(d4, occupied),
+            bitboard(&[d5, d6, d3, d2, d1, c4, b4, e4, f4, g4, h4]),
+        );
+    }
+
+    #[test]
+    fn queen() {
+        assert_eq!(queen_attacks(a1, BitboardEmpty).count(), 21);
+        assert_eq!(queen_attacks(d4, BitboardEmpty).count(), 27);
+        assert_eq!(queen_attacks(d4, BitboardFull).count(), 8);
+    }
+
+    #[test]
+    fn slider_tables_match_rays() {
+        for square in Square::iter() {
+            let bishop_mask = relevant_mask(square, &BISHOP_DIRECTIONS);
+            let rook_mask = relevant_mask(square, &ROOK_DIRECTIONS);
+
+            for occupied in [BitboardEmpty, BitboardFull, bishop_mask, rook_mask] {
+                assert_eq!(
+                    bishop_attacks(square, occupied),
+                    sliding_attacks(square, occupied, &BISHOP_DIRECTIONS),
+                );
+
+                assert_eq!(
+                    rook_attacks(square, occupied),
+                    sliding_attacks(square, occupied, &ROOK_DIRECTIONS),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod fuzz {
+    use super::*;
+
+    #[test]
+    fn slider_attacks() {
+        let mut rng = FastRng::from_system_time();
+
+        for i in 0..(11_010 * FUZZ_MULTIPLIER) {
+            // NOTE: sparse occupancy is closer to real positions
+            let occupied = Bitboard::rand(&mut rng) & Bitboard::rand(&mut rng);
+            let square = Square::rand(&mut rng);
+
+            assert_eq!(
+                bishop_attacks(square, occupied),
+                sliding_attacks(square, occupied, &BISHOP_DIRECTIONS),
+            );
+
+            assert_eq!(
+                rook_attacks(square, occupied),
+                sliding_attacks(square, occupied, &ROOK_DIRECTIONS),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use super::*;
+
+    use test::{Bencher, black_box};
+
+    #[bench]
+    fn init(b: &mut Bencher) {
+        b.iter(SliderAttacks::new)
+    }
+
+    #[bench]
+    fn queen_table(b: &mut Bencher) {
+        init_attacks();
+        let board = Board::from_fen(b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        b.iter(|| {
+            let occupied = black_box(board.occupied());
+
+            let mut result = BitboardEmpty;
+            for square in Square::iter() {
+                result ^= queen_attacks(square, occupied);
+            }
+
+            result
+        })
+    }
+
+    #[bench]
+    fn queen_rays(b: &mut Bencher) {
+        let board = Board::from_fen(b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        b.iter(|| {
+            let occupied = black_box(board.occupied());
+
+            let mut result = BitboardEmpty;
+            for square in Square::iter() {
+                result ^= sliding_attacks(square, occupied, &BISHOP_DIRECTIONS);
+                result ^= sliding_attacks(square, occupied, &ROOK_DIRECTIONS);
+            }
+
+            result
+        })
+    }
+}
@@ -0,0 +1,436 @@
+use std::fmt;
+use std::ops;
+
+use crate::prelude::*;
+
+pub type BitboardInner = u64;
+
+// Bit structure:
+// one bit per square, bit index is the same as `Square::index`,
+// so a1 is the least significant bit and h8 is the most significant one
+#[derive(Copy, Eq)]
+#[derive_const(Clone, PartialEq)]
+pub struct Bitboard(BitboardInner);
+
+impl Bitboard {
+    #[inline(always)]
+    pub const fn from_inner(inner: BitboardInner) -> Self {
+        Self(inner)
+    }
+
+    #[inline(always)]
+    pub const fn from_square(square: Square) -> Self {
+        Self(1 << square.index())
+    }
+
+    #[inline(always)]
+    pub const fn from_file(file: File) -> Self {
+        always!(!file.is_en_passant_none());
+
+        Self(BitboardFileA.0 << file.index())
+    }
+
+    #[inline(always)]
+    pub const fn from_rank(rank: Rank) -> Self {
+        Self(BitboardRank1.0 << rank.index())
+    }
+
+    #[inline(always)]
+    pub const fn inner(self) -> BitboardInner {
+        self.0
+    }
+
+    #[inline(always)]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline(always)]
+    pub const fn is_not_empty(self) -> bool {
+        self.0 != 0
+    }
+
+    #[inline(always)]
+    pub const fn has_many(self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    #[inline(always)]
+    pub const fn has(self, square: Square) -> bool {
+        self.0 & Self::from_square(square).0 != 0
+    }
+
+    #[inline(always)]
+    pub const fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, square: Square) {
+        self.0 |= Self::from_square(square).0;
+    }
+
+    #[inline(always)]
+    pub fn unset(&mut self, square: Square) {
+        self.0 &= !Self::from_square(square).0;
+    }
+
+    #[inline(always)]
+    pub fn toggle(&mut self, square: Square) {
+        self.0 ^= Self::from_square(square).0;
+    }
+
+    // Returns square of least significant bit
+    #[inline(always)]
+    pub const fn first(self) -> Square {
+        always!(self.is_not_empty());
+
+        Square::from_index(self.0.trailing_zeros() as SquareInner)
+    }
+
+    // Returns square of most significant bit
+    #[inline(always)]
+    pub const fn last(self) -> Square {
+        always!(self.is_not_empty());
+
+        Square::from_index(63 - self.0.leading_zeros() as SquareInner)
+    }
+
+    #[inline(always)]
+    pub fn pop_first(&mut self) -> Square {
+        let result = self.first();
+        self.0 &= self.0.wrapping_sub(1);
+
+        result
+    }
+
+    #[inline(always)]
+    pub const fn iter(self) -> BitboardIterator {
+        BitboardIterator(self)
+    }
+
+    // NOTE: shifts drop squares that go off the board,
+    //       they never wrap to the other side
+    #[inline(always)]
+    pub const fn up(self, by: SquareInner) -> Self {
+        always!(by < 8);
+
+        Self(self.0 << (by * 8))
+    }
+
+    #[inline(always)]
+    pub const fn down(self, by: SquareInner) -> Self {
+        always!(by < 8);
+
+        Self(self.0 >> (by * 8))
+    }
+
+    #[inline(always)]
+    pub const fn left(self) -> Self {
+        Self((self.0 & !BitboardFileA.0) >> 1)
+    }
+
+    #[inline(always)]
+    pub const fn right(self) -> Self {
+        Self((self.0 & !BitboardFileH.0) << 1)
+    }
+
+    // Moves black pieces toward rank 1
+    // And white pieces toward rank 8
+    #[inline(always)]
+    pub const fn forward(self, side_to_move: Color, by: SquareInner) -> Self {
+        match side_to_move {
+            Black => self.down(by),
+            White => self.up(by),
+            _ => never!(),
+        }
+    }
+
+    #[cfg(test)]
+    #[inline(always)]
+    pub fn rand(rng: &mut FastRng) -> Self {
+        Self(rng.rand_u64())
+    }
+}
+
+impl ops::BitAnd for Bitboard {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl ops::BitOr for Bitboard {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl ops::BitXor for Bitboard {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl ops::Not for Bitboard {
+    type Output = Self;
+
+    #[inline(always)]
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+impl ops::BitAndAssign for Bitboard {
+    #[inline(always)]
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl ops::BitOrAssign for Bitboard {
+    #[inline(always)]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl ops::BitXorAssign for Bitboard {
+    #[inline(always)]
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
+}
+
+impl fmt::Debug for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for rank in Rank::top_to_bottom() {
+            write!(f, " {} |", rank.fen() as char)?;
+
+            for file in File::a_to_h() {
+                let square = Square::from_file_rank(file, rank);
+
+                if self.has(square) {
+                    write!(f, " x ")?;
+                } else {
+                    write!(f, " . ")?;
+                }
+            }
+
+            writeln!(f)?;
+        }
+
+        writeln!(f, "     -  -  -  -  -  -  -  -")?;
+        write!(f, "     a  b  c  d  e  f  g  h")?;
+
+        Ok(())
+    }
+}
+
+// Iterates over squares from a1 to h8
+pub struct BitboardIterator(Bitboard);
+
+impl Iterator for BitboardIterator {
+    type Item = Square;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None
+        }
+
+        Some(self.0.pop_first())
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count() as usize;
+
+        (count, Some(count))
+    }
+}
+
+pub const BitboardEmpty: Bitboard = Bitboard(0);
+pub const BitboardFull: Bitboard = Bitboard(!0);
+
+pub const BitboardFileA: Bitboard = Bitboard(0x0101_0101_0101_0101);
+pub const BitboardFileH: Bitboard = Bitboard(0x8080_8080_8080_8080);
+
+pub const BitboardRank1: Bitboard = Bitboard(0x0000_0000_0000_00FF);
+pub const BitboardRank8: Bitboard = Bitboard(0xFF00_0000_0000_0000);
+
+// NOTE: a1 is dark square
+pub const BitboardLightSquares: Bitboard = Bitboard(0x55AA_55AA_55AA_55AA);
+pub const BitboardDarkSquares: Bitboard = Bitboard(0xAA55_AA55_AA55_AA55);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_layout() {
+        assert_eq!(Bitboard::from_square(a1).inner(), 1);
+        assert_eq!(Bitboard::from_square(h1).inner(), 1 << 7);
+        assert_eq!(Bitboard::from_square(a8).inner(), 1 << 56);
+        assert_eq!(Bitboard::from_square(h8).inner(), 1 << 63);
+
+        for square in Square::iter() {
+            let bitboard = Bitboard::from_square(square);
+
+            assert_eq!(bitboard.count(), 1);
+            assert_eq!(bitboard.first(), square);
+            assert_eq!(bitboard.last(), square);
+        }
+    }
+
+    #[test]
+    fn files_and_ranks() {
+        assert_eq!(Bitboard::from_file(FileA), BitboardFileA);
+        assert_eq!(Bitboard::from_file(FileH), BitboardFileH);
+        assert_eq!(Bitboard::from_rank(Rank1), BitboardRank1);
+        assert_eq!(Bitboard::from_rank(Rank8), BitboardRank8);
+
+        assert!(BitboardDarkSquares.has(a1));
+        assert!(BitboardLightSquares.has(h1));
+        assert!(BitboardLightSquares.has(a8));
+        assert!(BitboardDarkSquares.has(h8));
+        assert_eq!(BitboardLightSquares, !BitboardDarkSquares);
+
+        let file_e = Bitboard::from_file(FileE);
+        assert_eq!(file_e.count(), 8);
+        assert!(file_e.has(e1));
+        assert!(file_e.has(e8));
+        assert!(!file_e.has(d4));
+
+        let rank_4 = Bitboard::from_rank(Rank4);
+        assert_eq!(rank_4.count(), 8);
+        assert!(rank_4.has(a4));
+        assert!(rank_4.has(h4));
+        assert!(!rank_4.has(a5));
+    }
+
+    #[test]
+    fn set_unset_toggle() {
+        let mut bitboard = BitboardEmpty;
+        assert!(bitboard.is_empty());
+
+        bitboard.set(e4);
+        bitboard.set(e4);
+        assert!(bitboard.has(e4));
+        assert!(!bitboard.has_many());
+
+        bitboard.toggle(d5);
+        assert!(bitboard.has(d5));
+        assert!(bitboard.has_many());
+        assert_eq!(bitboard.count(), 2);
+
+        bitboard.unset(e4);
+        bitboard.toggle(d5);
+        assert!(bitboard.is_empty());
+    }
+
+    #[test]
+    fn iteration() {
+        let bitboard =
+            Bitboard::from_square(h8) |
+            Bitboard::from_square(a1) |
+            Bitboard::from_square(e4);
+
+        let squares: Vec<_> = bitboard.iter().collect();
+        assert_eq!(squares, vec![a1, e4, h8]);
+
+        assert_eq!(BitboardEmpty.iter().count(), 0);
+        assert_eq!(BitboardFull.iter().count(), 64);
+        assert!(BitboardFull.iter().eq(Square::iter()));
+    }
+
+    #[test]
+    fn shifts() {
+        let e4_bitboard = Bitboard::from_square(e4);
+
+        assert_eq!(e4_bitboard.up(1), Bitboard::from_square(e5));
+        assert_eq!(e4_bitboard.down(2), Bitboard::from_square(e2));
+        assert_eq!(e4_bitboard.left(), Bitboard::from_square(d4));
+        assert_eq!(e4_bitboard.right(), Bitboard::from_square(f4));
+
+        assert_eq!(e4_bitboard.forward(White, 1), Bitboard::from_square(e5));
+        assert_eq!(e4_bitboard.forward(Black, 1), Bitboard::from_square(e3));
+
+        // NOTE: shifts don't wrap around the board
+        assert!(BitboardFileA.left().is_empty());
+        assert!(BitboardFileH.right().is_empty());
+        assert!(BitboardRank8.up(1).is_empty());
+        assert!(BitboardRank1.down(1).is_empty());
+
+        assert_eq!(BitboardFull.left().count(), 56);
+        assert_eq!(BitboardFull.right().count(), 56);
+    }
+
+    #[test]
+    fn operators() {
+        let a = Bitboard::from_square(a1) | Bitboard::from_square(b1);
+        let b = Bitboard::from_square(b1) | Bitboard::from_square(c1);
+
+        assert_eq!(a & b, Bitboard::from_square(b1));
+        assert_eq!((a ^ b).count(), 2);
+        assert_eq!((!a).count(), 62);
+
+        let mut c = a;
+        c &= b;
+        c |= Bitboard::from_square(h8);
+        c ^= Bitboard::from_square(b1);
+        assert_eq!(c, Bitboard::from_square(h8));
+    }
+}
+
+#[cfg(test)]
+mod fuzz {
+    use super::*;
+
+    #[test]
+    fn iteration() {
+        let mut rng = FastRng::from_system_time();
+
+        for i in 0..(11_010 * FUZZ_MULTIPLIER) {
+            let bitboard = Bitboard::rand(&mut rng);
+
+            let mut collected = BitboardEmpty;
+            for square in bitboard.iter() {
+                assert!(!collected.has(square));
+                collected.set(square);
+            }
+
+            assert_eq!(collected, bitboard);
+            assert_eq!(bitboard.iter().count() as u32, bitboard.count());
+        }
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use super::*;
+
+    use test::{Bencher, black_box};
+
+    #[bench]
+    fn iterate(b: &mut Bencher) {
+        let bitboard = Bitboard::from_inner(0x00FF_0000_0000_FF00);
+
+        b.iter(|| {
+            let mut result: SquareInner = 0;
+            for square in black_box(bitboard).iter() {
+                result ^= square.index();
+            }
+
+            result
+        })
+    }
+}
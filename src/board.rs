@@ -2,22 +2,104 @@ use crate::prelude::*;
 use std::fmt;
 
 type HalfmoveClock = u16; // PERF: try smaller and bigger types
+type FullmoveCounter = u16;
 type Ply = usize; // PERF: try smaller and bigger types
 
 const MAX_HALFMOVE_CLOCK: HalfmoveClock = 999;
-const MIN_FEN_SIZE: usize = 24;
+const MAX_FULLMOVE_COUNTER: FullmoveCounter = 9999;
+
+// NOTE: relaxed fen without clocks is the shortest one
+const MIN_FEN_SIZE: usize = 21;
 const MAX_FEN_SIZE: usize = 90;
 
 const UNDO_STACK_LENGTH: usize = MAX_SEARCH_DEPTH;
 
+// NOTE: indexed by `CastlingRights::single_index`
+const STANDARD_CASTLING_ROOKS: [Square; 4] = [a8, h8, a1, h1];
+
 pub type FenBuffer = StaticBuffer::<u8, MAX_FEN_SIZE>;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FenField {
+    Position,
+    SideToMove,
+    CastlingRights,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveCounter,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FenErrorKind {
+    MissingField,
+    UnexpectedChar(u8),
+    TrailingChars,
+    BadPieceChar(u8),
+    RankTooLong,
+    RankTooShort,
+    TooFewRanks,
+    TooManyRanks,
+    BadSideToMove(u8),
+    BadCastlingFlag(u8),
+    ImpossibleCastlingFlag(u8),
+    DuplicateCastlingFlag(u8),
+    BadEnPassantSquare,
+    ImpossibleEnPassantSquare,
+    NonNumericClock,
+    ClockTooBig,
+    ImpossiblePawnStructure,
+    ImpossibleKingsSetup,
+    OpponentInCheck,
+}
+
+// Returned by `Board::try_from_fen`,
+// `offset` is index of offending byte in input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FenError {
+    pub field: FenField,
+    pub offset: usize,
+    pub kind: FenErrorKind,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid fen at byte {} ({:?} field): ", self.offset, self.field)?;
+
+        match self.kind {
+            FenErrorKind::MissingField => write!(f, "missing field"),
+            FenErrorKind::UnexpectedChar(c) => write!(f, "unexpected char {:?}", c as char),
+            FenErrorKind::TrailingChars => write!(f, "trailing chars after fullmove counter"),
+            FenErrorKind::BadPieceChar(c) => write!(f, "bad piece char {:?}", c as char),
+            FenErrorKind::RankTooLong => write!(f, "rank has more than 8 squares"),
+            FenErrorKind::RankTooShort => write!(f, "rank has less than 8 squares"),
+            FenErrorKind::TooFewRanks => write!(f, "position has less than 8 ranks"),
+            FenErrorKind::TooManyRanks => write!(f, "position has more than 8 ranks"),
+            FenErrorKind::BadSideToMove(c) => write!(f, "bad side to move {:?}", c as char),
+            FenErrorKind::BadCastlingFlag(c) => write!(f, "bad castling flag {:?}", c as char),
+            FenErrorKind::ImpossibleCastlingFlag(c) => write!(f, "no king or rook for castling flag {:?}", c as char),
+            FenErrorKind::DuplicateCastlingFlag(c) => write!(f, "duplicate castling flag {:?}", c as char),
+            FenErrorKind::BadEnPassantSquare => write!(f, "bad en passant square"),
+            FenErrorKind::ImpossibleEnPassantSquare => write!(f, "impossible en passant square"),
+            FenErrorKind::NonNumericClock => write!(f, "clock is not a number"),
+            FenErrorKind::ClockTooBig => write!(f, "clock is too big"),
+            FenErrorKind::ImpossiblePawnStructure => write!(f, "pawns on first or last rank"),
+            FenErrorKind::ImpossibleKingsSetup => write!(f, "every side should have exactly one king"),
+            FenErrorKind::OpponentInCheck => write!(f, "side not to move is in check"),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct Board {
     // PERF: try two arrays for dignities instead
     //       try to remove it from bitboard representation
     squares: [Piece; 64],
 
+    // Bitboards
+    // NOTE: both are kept in sync with squares
+    pieces: [Bitboard; Piece::TableSize],
+    colors: [Bitboard; 2],
+
     // PERF: try to merge flags
     side_to_move: Color,
 
@@ -34,8 +116,30 @@ pub struct Board {
     en_passant_file: [File; UNDO_STACK_LENGTH],
     halfmove_clock: [HalfmoveClock; UNDO_STACK_LENGTH],
 
+    // Castling setup, it's constant during the game
+    // NOTE: rooks are indexed by `CastlingRights::single_index`,
+    //       masks are rights lost when piece leaves or enters a square
+    castling_rooks: [Square; 4],
+    castling_masks: [CastlingRights; 64],
+
+    // NOTE: it isn't on undo stack, cause it's always
+    //       increased after black move and decreased back in unmake
+    fullmove_counter: FullmoveCounter,
+
     // Zobrist hashing
     hash_key: ZobristKey,
+
+    // Hashes of previous positions, last one is position before last move
+    // NOTE: unlike undo stacks it isn't limited by search depth
+    //       and survives `reset_ply`, so game moves are here too
+    // PERF: try fixed size ring buffer
+    key_history: Vec<ZobristKey>,
+
+    // Incremental evaluation
+    // NOTE: scores are from white point of view
+    middlegame_score: Score,
+    endgame_score: Score,
+    phase: Phase,
 }
 
 impl Board {
@@ -46,15 +150,30 @@ impl Board {
             squares: [PieceNone; 64],
             side_to_move: White,
 
+            // Bitboards
+            pieces: [BitboardEmpty; Piece::TableSize],
+            colors: [BitboardEmpty; 2],
+
             // Undo stacks
             ply: 0,
             castling_rights: [CastlingRightsNone; UNDO_STACK_LENGTH],
             en_passant_file: [FileEnPassantNone; UNDO_STACK_LENGTH],
             halfmove_clock: [0; UNDO_STACK_LENGTH],
+            fullmove_counter: 1,
+
+            // Castling
+            castling_rooks: STANDARD_CASTLING_ROOKS,
+            castling_masks: [CastlingRightsNone; 64],
 
             // Hashing
             // PERF: try to keep hash_key in undo table
             hash_key: ZobristKey::new(),
+            key_history: Vec::new(),
+
+            // Incremental evaluation
+            middlegame_score: 0,
+            endgame_score: 0,
+            phase: 0,
         }
     }
 
@@ -68,6 +187,9 @@ impl Board {
             squares: [PieceNone; 64],
             side_to_move: unsafe { undefined() },
 
+            pieces: [BitboardEmpty; Piece::TableSize],
+            colors: [BitboardEmpty; 2],
+
             // Undo stacks
             ply: 0,
 
@@ -75,8 +197,17 @@ impl Board {
             castling_rights: [CastlingRightsNone; UNDO_STACK_LENGTH],
             en_passant_file: [FileEnPassantNone; UNDO_STACK_LENGTH],
             halfmove_clock: [0; UNDO_STACK_LENGTH],
+            fullmove_counter: 1,
+
+            castling_rooks: STANDARD_CASTLING_ROOKS,
+            castling_masks: [CastlingRightsNone; 64],
 
             hash_key: ZobristKey::new(),
+            key_history: Vec::new(),
+
+            middlegame_score: 0,
+            endgame_score: 0,
+            phase: 0,
         };
 
         let mut fen_index: u8 = 0;
@@ -146,8 +277,23 @@ impl Board {
                     skip_char!(b' ');
                     break;
                 },
-                right => {
-                    result.castling_rights[0].set_from_fen(right);
+                flag => {
+                    // NOTE: impossible rights are kept as in standard chess
+                    match result.castling_from_fen(flag) {
+                        Some((right, king, rook)) => result.add_castling(right, king, rook),
+                        None => {
+                            always!(matches!(flag, b'K' | b'Q' | b'k' | b'q'));
+
+                            let right = match flag {
+                                b'K' => WhiteKingSide,
+                                b'Q' => WhiteQueenSide,
+                                b'k' => BlackKingSide,
+                                _ => BlackQueenSide,
+                            };
+
+                            result.add_standard_castling(right);
+                        },
+                    }
                 },
             }
 
@@ -158,7 +304,6 @@ impl Board {
         if fen_char!() == b'-' {
             // NOTE: en_passant_file[0] is already None
             fen_index += 1;
-            skip_char!(b' ');
         } else {
             result.en_passant_file[0] = File::from_fen(fen_char!());
             fen_index += 1;
@@ -168,19 +313,23 @@ impl Board {
             } else {
                 skip_char!(b'3');
             }
+        }
 
-            skip_char!(b' ');
+        // NOTE: relaxed fen ends here, clocks are
+        //       already 0 and 1 in that case
+        if fen_index as usize == fen.len() {
+            return result;
         }
 
+        skip_char!(b' ');
+
         // 5. Halfmove clock
         // NOTE: halfmove_clock[0] is 0 already
         // PERF: unroll loop
         // PERF: use array with powers of 10
         loop {
             if fen_char!() == b' ' {
-                // NOTE: we don't move forward (fen_index += 1),
-                //       cause we don't parse fullmove counter
-                //       at all
+                fen_index += 1;
                 break;
             }
 
@@ -197,17 +346,247 @@ impl Board {
         }
 
         // 6. Fullmove counter
-        // NOTE: fullmove counter is ignored
+        result.fullmove_counter = 0;
+        while (fen_index as usize) < fen.len() {
+            let digit = fen_char!();
+            always!(digit.is_ascii_digit());
+
+            result.fullmove_counter *= 10;
+            result.fullmove_counter += (digit ^ 0b110000) as FullmoveCounter;
+
+            fen_index += 1;
+        }
 
         result
     }
 
+    // Safe version of `from_fen`: every byte is checked
+    // and impossible positions are rejected
+    // NOTE: it's much slower, so use it only for untrusted input
+    pub fn try_from_fen(fen: &[u8]) -> Result<Self, FenError> {
+        let mut result = Self::empty();
+        let mut fen_index: usize = 0;
+
+        macro_rules! fail {
+            ($field:expr, $kind:expr) => {
+                fail!($field, fen_index, $kind)
+            };
+            ($field:expr, $offset:expr, $kind:expr) => {
+                return Err(FenError {
+                    field: $field,
+                    offset: $offset,
+                    kind: $kind,
+                })
+            };
+        }
+
+        // Skips space before `$field`
+        macro_rules! skip_separator {
+            ($field:expr) => {
+                match fen.get(fen_index) {
+                    Some(b' ') => fen_index += 1,
+                    Some(&c) => fail!($field, FenErrorKind::UnexpectedChar(c)),
+                    None => fail!($field, FenErrorKind::MissingField),
+                }
+            }
+        }
+
+        macro_rules! parse_clock {
+            ($field:expr, $max:expr) => {{
+                let start = fen_index;
+                let mut value: usize = 0;
+
+                while let Some(&c) = fen.get(fen_index) {
+                    if c == b' ' {
+                        break;
+                    }
+
+                    if !c.is_ascii_digit() {
+                        fail!($field, FenErrorKind::NonNumericClock);
+                    }
+
+                    value = value * 10 + (c - b'0') as usize;
+                    if value > $max {
+                        fail!($field, start, FenErrorKind::ClockTooBig);
+                    }
+
+                    fen_index += 1;
+                }
+
+                if fen_index == start {
+                    fail!($field, FenErrorKind::MissingField);
+                }
+
+                value
+            }}
+        }
+
+        // 1. Position
+        for rank in Rank::top_to_bottom() {
+            let mut file: u8 = 0;
+
+            loop {
+                match fen.get(fen_index) {
+                    Some(&c @ b'1'..=b'8') => {
+                        file += c - b'0';
+                        if file > 8 {
+                            fail!(FenField::Position, FenErrorKind::RankTooLong);
+                        }
+                    },
+                    Some(&c @ (b'p' | b'n' | b'b' | b'r' | b'q' | b'k' |
+                               b'P' | b'N' | b'B' | b'R' | b'Q' | b'K')) => {
+                        if file == 8 {
+                            fail!(FenField::Position, FenErrorKind::RankTooLong);
+                        }
+
+                        let square = Square::from_file_rank(File::from_index(file), rank);
+                        result.set_piece_unchecked(square, Piece::from_fen(c));
+                        file += 1;
+                    },
+                    Some(b'/' | b' ') | None => break,
+                    Some(&c) => fail!(FenField::Position, FenErrorKind::BadPieceChar(c)),
+                }
+
+                fen_index += 1;
+            }
+
+            if file < 8 {
+                fail!(FenField::Position, FenErrorKind::RankTooShort);
+            }
+
+            if rank != Rank1 {
+                if fen.get(fen_index) != Some(&b'/') {
+                    fail!(FenField::Position, FenErrorKind::TooFewRanks);
+                }
+
+                fen_index += 1;
+            } else if fen.get(fen_index) == Some(&b'/') {
+                fail!(FenField::Position, FenErrorKind::TooManyRanks);
+            }
+        }
+
+        // 2. Side to move
+        skip_separator!(FenField::SideToMove);
+        match fen.get(fen_index) {
+            Some(&c @ (b'w' | b'b')) => result.side_to_move = Color::from_fen(c),
+            Some(&c) => fail!(FenField::SideToMove, FenErrorKind::BadSideToMove(c)),
+            None => fail!(FenField::SideToMove, FenErrorKind::MissingField),
+        }
+        fen_index += 1;
+
+        // 3. Castling rights
+        skip_separator!(FenField::CastlingRights);
+        if fen.get(fen_index) == Some(&b'-') {
+            fen_index += 1;
+        } else {
+            let start = fen_index;
+
+            // NOTE: both X-FEN (KQkq) and Shredder-FEN (file letters) are supported
+            while let Some(&c) = fen.get(fen_index) {
+                if c == b' ' {
+                    break;
+                }
+
+                if !matches!(c, b'K' | b'Q' | b'k' | b'q' | b'A'..=b'H' | b'a'..=b'h') {
+                    fail!(FenField::CastlingRights, FenErrorKind::BadCastlingFlag(c));
+                }
+
+                let (right, king, rook) = match result.castling_from_fen(c) {
+                    Some(castling) => castling,
+                    None => fail!(FenField::CastlingRights, FenErrorKind::ImpossibleCastlingFlag(c)),
+                };
+
+                if result.castling_rights[0].is_allowed(right) {
+                    fail!(FenField::CastlingRights, FenErrorKind::DuplicateCastlingFlag(c));
+                }
+
+                result.add_castling(right, king, rook);
+                fen_index += 1;
+            }
+
+            if fen_index == start {
+                fail!(FenField::CastlingRights, FenErrorKind::MissingField);
+            }
+        }
+
+        // 4. En passant target square
+        skip_separator!(FenField::EnPassant);
+        let en_passant_index = fen_index;
+        if fen.get(fen_index) == Some(&b'-') {
+            fen_index += 1;
+        } else {
+            let expected_rank = match result.side_to_move {
+                White => b'6',
+                _ => b'3',
+            };
+
+            match (fen.get(fen_index), fen.get(fen_index + 1)) {
+                (Some(&file @ b'a'..=b'h'), Some(&rank)) if rank == expected_rank => {
+                    result.en_passant_file[0] = File::from_fen(file);
+                },
+                (Some(b'a'..=b'h'), Some(b'1'..=b'8')) => {
+                    fail!(FenField::EnPassant, FenErrorKind::ImpossibleEnPassantSquare);
+                },
+                (None, _) => fail!(FenField::EnPassant, FenErrorKind::MissingField),
+                _ => fail!(FenField::EnPassant, FenErrorKind::BadEnPassantSquare),
+            }
+
+            fen_index += 2;
+        }
+
+        // 5. Halfmove clock
+        // NOTE: relaxed fen without both clocks is allowed,
+        //       clocks are already 0 and 1 in that case
+        if fen_index != fen.len() {
+            skip_separator!(FenField::HalfmoveClock);
+            result.halfmove_clock[0] = parse_clock!(
+                FenField::HalfmoveClock,
+                MAX_HALFMOVE_CLOCK as usize
+            ) as HalfmoveClock;
+
+            // 6. Fullmove counter
+            skip_separator!(FenField::FullmoveCounter);
+            result.fullmove_counter = parse_clock!(
+                FenField::FullmoveCounter,
+                MAX_FULLMOVE_COUNTER as usize
+            ) as FullmoveCounter;
+
+            if fen_index != fen.len() {
+                fail!(FenField::FullmoveCounter, FenErrorKind::TrailingChars);
+            }
+        }
+
+        // 7. Position validation
+        // NOTE: these errors are about the whole placement field,
+        //       so they point to its start, which is offset 0
+        if !result.has_possible_pawn_structure() {
+            fail!(FenField::Position, 0, FenErrorKind::ImpossiblePawnStructure);
+        }
+
+        if !result.has_possible_kings_setup() {
+            fail!(FenField::Position, 0, FenErrorKind::ImpossibleKingsSetup);
+        }
+
+        // NOTE: needs exactly one king of each side, so goes after kings check
+        if !result.has_possible_checks() {
+            fail!(FenField::Position, 0, FenErrorKind::OpponentInCheck);
+        }
+
+        if !result.has_possible_en_passant_square() {
+            fail!(FenField::EnPassant, en_passant_index, FenErrorKind::ImpossibleEnPassantSquare);
+        }
+
+        Ok(result)
+    }
+
     #[inline(always)]
     pub fn find_piece(&self, piece: Piece) -> Option<Square> {
-        // PERF:: check if manual implementation is faster
-        Square::iter().find(|&square| {
-            self.piece(square) == piece
-        })
+        let pieces = self.pieces(piece);
+        if pieces.is_empty() {
+            return None
+        }
+
+        Some(pieces.first())
     }
 
     #[inline(always)]
@@ -220,6 +599,21 @@ impl Board {
         get_unchecked!(self.squares, at.index())
     }
 
+    #[inline(always)]
+    pub const fn pieces(&self, piece: Piece) -> Bitboard {
+        get_unchecked!(self.pieces, piece.index())
+    }
+
+    #[inline(always)]
+    pub const fn color_pieces(&self, color: Color) -> Bitboard {
+        get_unchecked!(self.colors, color.index())
+    }
+
+    #[inline(always)]
+    pub fn occupied(&self) -> Bitboard {
+        self.color_pieces(Black) | self.color_pieces(White)
+    }
+
     #[inline(always)]
     pub const fn side_to_move(&self) -> Color {
         self.side_to_move
@@ -230,6 +624,18 @@ impl Board {
         get_unchecked!(self.castling_rights, self.ply)
     }
 
+    // Initial square of rook for single castling right
+    #[inline(always)]
+    pub const fn castling_rook(&self, right: CastlingRights) -> Square {
+        get_unchecked!(self.castling_rooks, right.single_index())
+    }
+
+    // Castling rights lost when piece leaves or enters `square`
+    #[inline(always)]
+    pub const fn castling_mask(&self, square: Square) -> CastlingRights {
+        get_unchecked!(self.castling_masks, square.index())
+    }
+
     #[inline(always)]
     pub const fn en_passant_file(&self) -> File {
         get_unchecked!(self.en_passant_file, self.ply)
@@ -240,6 +646,111 @@ impl Board {
         get_unchecked!(self.halfmove_clock, self.ply)
     }
 
+    #[inline(always)]
+    pub const fn fullmove_counter(&self) -> FullmoveCounter {
+        self.fullmove_counter
+    }
+
+    #[inline(always)]
+    pub const fn middlegame_score(&self) -> Score {
+        self.middlegame_score
+    }
+
+    #[inline(always)]
+    pub const fn endgame_score(&self) -> Score {
+        self.endgame_score
+    }
+
+    #[inline(always)]
+    pub const fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    // Finds castling right and rook of X-FEN or Shredder-FEN castling flag,
+    // X-FEN letters mean outermost rook on that side of king
+    fn castling_from_fen(&self, flag: u8) -> Option<(CastlingRights, Square, Square)> {
+        let color = if flag.is_ascii_uppercase() { White } else { Black };
+        let rank = color.start_rank();
+
+        let king = self.pieces(Piece::new(color, King));
+        if king.count() != 1 || king.first().rank() != rank {
+            return None;
+        }
+
+        let king = king.first();
+        let rooks = self.pieces(Piece::new(color, Rook)) & Bitboard::from_rank(rank);
+
+        let rook = match flag.to_ascii_lowercase() {
+            b'k' => rooks.iter().filter(|rook| rook.index() > king.index()).last()?,
+            b'q' => rooks.iter().find(|rook| rook.index() < king.index())?,
+            file @ b'a'..=b'h' => {
+                let rook = Square::from_file_rank(File::from_fen(file), rank);
+                if !rooks.has(rook) {
+                    return None;
+                }
+
+                rook
+            },
+            _ => return None,
+        };
+
+        let right = if rook.index() > king.index() {
+            CastlingRights::king_side(color)
+        } else {
+            CastlingRights::queen_side(color)
+        };
+
+        Some((right, king, rook))
+    }
+
+    fn add_castling(&mut self, right: CastlingRights, king: Square, rook: Square) {
+        self.castling_rights[0].allow(right);
+        self.castling_rooks[right.single_index()] = rook;
+        self.castling_masks[king.index() as usize].allow(right);
+        self.castling_masks[rook.index() as usize].allow(right);
+    }
+
+    fn add_standard_castling(&mut self, right: CastlingRights) {
+        let king = Square::from_file_rank(FileE, right.color().start_rank());
+        let rook = get_unchecked!(STANDARD_CASTLING_ROOKS, right.single_index());
+
+        self.add_castling(right, king, rook);
+    }
+
+    // Writes X-FEN castling rights, so standard positions get usual KQkq
+    fn castling_fen(&self, buffer: &mut FenBuffer) {
+        let rights = self.castling_rights();
+        if rights == CastlingRightsNone {
+            buffer.add(b'-');
+            return;
+        }
+
+        for right in CastlingRightsSingle {
+            if !rights.is_allowed(right) {
+                continue;
+            }
+
+            let rook = self.castling_rook(right);
+            let outer_rooks = self.pieces(Piece::new(right.color(), Rook))
+                .iter()
+                .filter(|other| other.rank() == rook.rank())
+                .filter(|other| if right.fen().eq_ignore_ascii_case(&b'k') {
+                    other.index() > rook.index()
+                } else {
+                    other.index() < rook.index()
+                });
+
+            let is_edge = rook.file() == FileA || rook.file() == FileH;
+            if is_edge || outer_rooks.count() == 0 {
+                buffer.add(right.fen());
+            } else if right.color() == White {
+                buffer.add(rook.file().fen().to_ascii_uppercase());
+            } else {
+                buffer.add(rook.file().fen());
+            }
+        }
+    }
+
     #[inline(always)]
     pub fn fen(&self, buffer: &mut FenBuffer) {
         // 1. Position
@@ -276,7 +787,7 @@ impl Board {
         buffer.add(b' ');
 
         // 3. Castling rights
-        self.castling_rights().fen(buffer);
+        self.castling_fen(buffer);
         buffer.add(b' ');
 
         // 4. En passant target square
@@ -294,27 +805,14 @@ impl Board {
         let hmc = self.halfmove_clock();
         always!(hmc <= MAX_HALFMOVE_CLOCK);
 
-        let x = (hmc / 100 % 10) as u8;
-        let y = (hmc / 10 % 10) as u8;
-        let z = (hmc % 10) as u8;
-
-        if x != 0 {
-            // NOTE: b'0' ^ x is equivalent of b'0' + x (if 0 <= x <= 9)
-            buffer.add(b'0' ^ x);
-            buffer.add(b'0' ^ y);
-            buffer.add(b'0' ^ z);
-        } else if y != 0 {
-            buffer.add(b'0' ^ y);
-            buffer.add(b'0' ^ z);
-        } else {
-            buffer.add(b'0' ^ z);
-        }
-
+        add_fen_number(buffer, hmc);
         buffer.add(b' ');
 
         // 6. Fullmove counter
-        // NOTE: isn't used
-        buffer.add(b'1');
+        let fmc = self.fullmove_counter();
+        always!(fmc <= MAX_FULLMOVE_COUNTER);
+
+        add_fen_number(buffer, fmc);
     }
 
     // Creates random board, using `rng`
@@ -335,10 +833,10 @@ impl Board {
         if rng.rand_bool() { result.side_to_move = Black }
 
         // 3. Castling rights
-        if rng.rand_bool() { result.castling_rights[0].allow(BlackKingSide) }
-        if rng.rand_bool() { result.castling_rights[0].allow(BlackQueenSide) }
-        if rng.rand_bool() { result.castling_rights[0].allow(WhiteKingSide) }
-        if rng.rand_bool() { result.castling_rights[0].allow(WhiteQueenSide) }
+        if rng.rand_bool() { result.add_standard_castling(BlackKingSide) }
+        if rng.rand_bool() { result.add_standard_castling(BlackQueenSide) }
+        if rng.rand_bool() { result.add_standard_castling(WhiteKingSide) }
+        if rng.rand_bool() { result.add_standard_castling(WhiteQueenSide) }
 
         // 4. En passant target square
         if rng.rand_bool() {
@@ -349,7 +847,7 @@ impl Board {
         result.halfmove_clock[0] = rng.rand_range_u16(0, MAX_HALFMOVE_CLOCK);
 
         // 6. Fullmove counter
-        // NOTE: isn't needed
+        result.fullmove_counter = rng.rand_range_u16(1, MAX_FULLMOVE_COUNTER);
 
         result
     }
@@ -376,6 +874,16 @@ impl Board {
         );
         self.hash_key.mut_xor(hash_change);
 
+        self.middlegame_score += get_unchecked_2d!(PIECE_SQUARE_TO_MIDDLEGAME, piece_index, at_index);
+        self.endgame_score += get_unchecked_2d!(PIECE_SQUARE_TO_ENDGAME, piece_index, at_index);
+        self.phase += get_unchecked!(PIECE_TO_PHASE, piece_index);
+
+        // PERF: try to keep only dignity bitboards
+        let at_bitboard = Bitboard::from_square(at);
+        let color_index = piece.color().index();
+        set_unchecked!(self.pieces, piece_index, get_unchecked!(self.pieces, piece_index) | at_bitboard);
+        set_unchecked!(self.colors, color_index, get_unchecked!(self.colors, color_index) | at_bitboard);
+
         set_unchecked!(self.squares, at_index, piece);
     }
 
@@ -397,6 +905,15 @@ impl Board {
         );
         self.hash_key.mut_xor(hash_change);
 
+        self.middlegame_score -= get_unchecked_2d!(PIECE_SQUARE_TO_MIDDLEGAME, piece_index, at_index);
+        self.endgame_score -= get_unchecked_2d!(PIECE_SQUARE_TO_ENDGAME, piece_index, at_index);
+        self.phase -= get_unchecked!(PIECE_TO_PHASE, piece_index);
+
+        let at_bitboard = Bitboard::from_square(at);
+        let color_index = removed_piece.color().index();
+        set_unchecked!(self.pieces, piece_index, get_unchecked!(self.pieces, piece_index) ^ at_bitboard);
+        set_unchecked!(self.colors, color_index, get_unchecked!(self.colors, color_index) ^ at_bitboard);
+
         set_unchecked!(self.squares, at_index, PieceNone);
     }
 
@@ -405,8 +922,12 @@ impl Board {
         self.side_to_move.swap()
     }
 
+    // NOTE: should be called before move changes the board,
+    //       cause it saves hash of current position
     #[inline(always)]
     pub fn push_undo(&mut self) {
+        self.key_history.push(self.hash());
+
         let prev_ply = self.ply;
         self.ply += 1;
 
@@ -420,9 +941,53 @@ impl Board {
     pub fn pop_undo(&mut self) {
         always!(self.ply > 0);
 
+        self.key_history.pop();
         self.ply -= 1;
     }
 
+    // Makes current position a root of undo stacks,
+    // so moves played in a game don't exhaust them
+    #[inline(always)]
+    pub fn reset_ply(&mut self) {
+        self.castling_rights[0] = self.castling_rights();
+        self.en_passant_file[0] = self.en_passant_file();
+        self.halfmove_clock[0] = self.halfmove_clock();
+        self.ply = 0;
+
+        // NOTE: positions before last irreversible move can't be repeated
+        let irreversible = self.key_history.len().saturating_sub(self.halfmove_clock() as usize);
+        self.key_history.drain(..irreversible);
+    }
+
+    // Counts previous occurrences of current position,
+    // looks back only until last capture or pawn move
+    #[inline(always)]
+    pub fn repetitions(&self) -> usize {
+        let hash = self.hash();
+        let plies_back = (self.halfmove_clock() as usize).min(self.key_history.len());
+
+        // NOTE: same position can be only with the same side to move,
+        //       so we check every second one, starting 2 plies ago
+        self.key_history[self.key_history.len() - plies_back..]
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .filter(|&&key| key == hash)
+            .count()
+    }
+
+    // Twofold repetition, enough to score position as a draw in search
+    #[inline(always)]
+    pub fn is_repetition(&self) -> bool {
+        self.repetitions() >= 1
+    }
+
+    #[inline(always)]
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 2
+    }
+
     #[inline(always)]
     pub fn disallow_castling(&mut self, rights: CastlingRights) {
         always!(self.ply < UNDO_STACK_LENGTH);
@@ -457,6 +1022,45 @@ impl Board {
         self.halfmove_clock[self.ply] += 1;
     }
 
+    #[inline(always)]
+    pub fn increase_fullmove_counter(&mut self) {
+        self.fullmove_counter += 1;
+    }
+
+    #[inline(always)]
+    pub fn decrease_fullmove_counter(&mut self) {
+        always!(self.fullmove_counter > 0);
+
+        self.fullmove_counter -= 1;
+    }
+
+    // Dead position by material: K vs K, K + minor vs K
+    // or any number of bishops all on the same color squares
+    #[inline(always)]
+    pub fn has_insufficient_material(&self) -> bool {
+        let heavy_or_pawns =
+            self.pieces(BlackPawn) | self.pieces(WhitePawn) |
+            self.pieces(BlackRook) | self.pieces(WhiteRook) |
+            self.pieces(BlackQueen) | self.pieces(WhiteQueen);
+
+        if heavy_or_pawns.is_not_empty() {
+            return false;
+        }
+
+        let knights = self.pieces(BlackKnight) | self.pieces(WhiteKnight);
+        let bishops = self.pieces(BlackBishop) | self.pieces(WhiteBishop);
+        let minors = knights | bishops;
+
+        if !minors.has_many() {
+            return true;
+        }
+
+        knights.is_empty() && (
+            (bishops & BitboardLightSquares).is_empty() ||
+            (bishops & BitboardDarkSquares).is_empty()
+        )
+    }
+
     #[inline(always)]
     pub fn has_possible_pawn_structure(&self) -> bool {
         for square in Square::iter() {
@@ -515,6 +1119,31 @@ impl Board {
         white_kings == 1 && black_kings == 1
     }
 
+    // Side which just moved can't be left in check,
+    // otherwise its king could be captured
+    #[inline(always)]
+    pub fn has_possible_checks(&self) -> bool {
+        let us = self.side_to_move();
+        let king = match self.find_king(us.swapped()) {
+            Some(king) => king,
+            None => return true,
+        };
+
+        let occupied = self.occupied();
+        let bishops = self.pieces(Piece::new(us, Bishop));
+        let rooks = self.pieces(Piece::new(us, Rook));
+        let queens = self.pieces(Piece::new(us, Queen));
+
+        let attackers =
+            (pawn_attacks(us.swapped(), king) & self.pieces(Piece::new(us, Pawn))) |
+            (knight_attacks(king) & self.pieces(Piece::new(us, Knight))) |
+            (bishop_attacks(king, occupied) & (bishops | queens)) |
+            (rook_attacks(king, occupied) & (rooks | queens)) |
+            (king_attacks(king) & self.pieces(Piece::new(us, King)));
+
+        attackers.is_empty()
+    }
+
     // PERF: try to incremental update additional changes
     // PERF: try to set ep_hash near hash_key incrementaly
     // PERF: try to use ep_file.index() / color.index() to remove conditions
@@ -565,6 +1194,22 @@ impl Board {
     }
 }
 
+// Writes number without leading zeros
+// PERF: try to unroll it like it was for halfmove clock
+#[inline(always)]
+fn add_fen_number(buffer: &mut FenBuffer, number: u16) {
+    let mut divisor = 1;
+    while number / divisor >= 10 {
+        divisor *= 10;
+    }
+
+    while divisor != 0 {
+        // NOTE: b'0' ^ x is equivalent of b'0' + x (if 0 <= x <= 9)
+        buffer.add(b'0' ^ (number / divisor % 10) as u8);
+        divisor /= 10;
+    }
+}
+
 impl fmt::Debug for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for rank in Rank::top_to_bottom() {
@@ -668,6 +1313,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_fen_chess960_castling() {
+        // NOTE: Shredder-FEN, rooks are given by files
+        let board = Board::from_fen(b"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+        assert_eq!(board.castling_rights(), CastlingRightsAll);
+        assert_eq!(board.castling_rook(WhiteKingSide), h1);
+        assert_eq!(board.castling_rook(WhiteQueenSide), f1);
+        assert_eq!(board.castling_rook(BlackKingSide), h8);
+        assert_eq!(board.castling_rook(BlackQueenSide), f8);
+
+        // NOTE: X-FEN, letters mean outermost rooks
+        let board = Board::from_fen(b"1r2k1r1/8/8/8/8/8/8/RR2K3 w Qkq - 0 1");
+        assert_eq!(board.castling_rook(WhiteQueenSide), a1);
+        assert_eq!(board.castling_rook(BlackKingSide), g8);
+        assert_eq!(board.castling_rook(BlackQueenSide), b8);
+
+        let board = Board::from_fen(b"4k3/8/8/8/8/8/8/RR2K3 w B - 0 1");
+        assert_eq!(board.castling_rook(WhiteQueenSide), b1);
+    }
+
+    #[test]
+    fn fen_chess960_castling() {
+        for (fen, expected) in [
+            (&b"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"[..], &b"KQkq"[..]),
+            (b"1r2k1r1/8/8/8/8/8/8/RR2K3 w Qkq - 0 1", b"Qkq"),
+            (b"4k3/8/8/8/8/8/8/RR2K3 w B - 0 1", b"B"),
+            (b"rr2k3/8/8/8/8/8/8/4K3 w b - 0 1", b"b"),
+        ] {
+            let board = Board::from_fen(fen);
+            let mut buffer = FenBuffer::new();
+            board.fen(&mut buffer);
+
+            let castling = buffer.as_slice().split(|&c| c == b' ').nth(2).unwrap();
+            assert_eq!(castling, expected);
+        }
+    }
+
     #[test]
     fn from_fen_en_passant() {
         for (fen, expected) in [
@@ -693,6 +1375,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn try_from_fen_valid() {
+        for fen in [
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".as_slice(),
+            b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            b"rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b Kq e3 0 3",
+            b"rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w kQ e6 0 3",
+            b"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 99 120",
+        ] {
+            assert_eq!(Board::try_from_fen(fen), Ok(Board::from_fen(fen)));
+        }
+    }
+
+    #[test]
+    fn try_from_fen_errors() {
+        use FenErrorKind::*;
+        use FenField::*;
+
+        for (fen, field, offset, kind) in [
+            ("", Position, 0, RankTooShort),
+            ("rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Position, 13, BadPieceChar(b'x')),
+            ("rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Position, 17, RankTooLong),
+            ("rnbqkbnr/pppppppp/45/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Position, 19, RankTooLong),
+            ("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Position, 16, RankTooShort),
+            ("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Position, 41, TooFewRanks),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/8 w KQkq - 0 1", Position, 43, TooManyRanks),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", SideToMove, 43, MissingField),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1", SideToMove, 44, BadSideToMove(b'x')),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1", CastlingRights, 48, BadCastlingFlag(b'x')),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkk - 0 1", CastlingRights, 49, DuplicateCastlingFlag(b'k')),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KHkq - 0 1", CastlingRights, 47, DuplicateCastlingFlag(b'H')),
+            ("4k3/8/8/8/8/8/8/4K3 w K - 0 1", CastlingRights, 22, ImpossibleCastlingFlag(b'K')),
+            ("r3k3/8/8/8/8/8/8/4K2R w Kkq - 0 1", CastlingRights, 25, ImpossibleCastlingFlag(b'k')),
+            ("r3k3/8/8/8/8/8/8/4K2R w Kb - 0 1", CastlingRights, 25, ImpossibleCastlingFlag(b'b')),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w  - 0 1", CastlingRights, 46, MissingField),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq i6 0 1", EnPassant, 51, BadEnPassantSquare),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1", EnPassant, 51, ImpossibleEnPassantSquare),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1", EnPassant, 51, ImpossibleEnPassantSquare),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1", HalfmoveClock, 53, NonNumericClock),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 1000 1", HalfmoveClock, 53, ClockTooBig),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0", FullmoveCounter, 54, MissingField),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1x", FullmoveCounter, 56, NonNumericClock),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ", FullmoveCounter, 56, TrailingChars),
+            ("rnbqkbnP/pppppppp/8/8/8/8/PPPPPPP1/RNBQKBNR w KQq - 0 1", Position, 0, ImpossiblePawnStructure),
+            ("rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1", Position, 0, ImpossibleKingsSetup),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKKNR w kq - 0 1", Position, 0, ImpossibleKingsSetup),
+            ("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1", Position, 0, OpponentInCheck),
+            ("4k3/3P4/8/8/8/8/8/6K1 w - - 0 1", Position, 0, OpponentInCheck),
+            ("8/8/8/8/8/8/5k2/4K3 b - - 0 1", Position, 0, OpponentInCheck),
+        ] {
+            assert_eq!(
+                Board::try_from_fen(fen.as_bytes()),
+                Err(FenError { field, offset, kind }),
+                "{fen}",
+            );
+        }
+    }
+
     #[test]
     fn to_fen_startpos() {
         let fen = b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
@@ -768,6 +1508,132 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fen_fullmove_counter() {
+        let examples: [&[u8]; _] = [
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 10",
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 7 42",
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 99 100",
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 999 9999",
+        ];
+
+        let mut buffer = FenBuffer::new();
+        for fen in examples {
+            let board = Board::from_fen(fen);
+            buffer.reset();
+            board.fen(&mut buffer);
+
+            assert_eq!(buffer.as_slice(), fen);
+            assert_eq!(Board::try_from_fen(fen), Ok(board));
+        }
+    }
+
+    #[test]
+    fn fen_without_clocks() {
+        let examples: [&[u8]; _] = [
+            b"8/8/8/8/8/8/8/8 w - -",
+            b"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3",
+        ];
+
+        let mut buffer = FenBuffer::new();
+        for fen in examples {
+            let board = Board::from_fen(fen);
+            buffer.reset();
+            board.fen(&mut buffer);
+
+            assert_eq!(buffer.as_slice(), [fen, b" 0 1".as_slice()].concat());
+            assert_eq!(board.halfmove_clock(), 0);
+            assert_eq!(board.fullmove_counter(), 1);
+        }
+
+        assert_eq!(
+            Board::try_from_fen(b"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3"),
+            Ok(Board::from_fen(b"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")),
+        );
+    }
+
+    #[test]
+    fn repetitions() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let movegen = MoveGenerator::new();
+        let shuffle = [
+            Move::quiet(g1, f3),
+            Move::quiet(g8, f6),
+            Move::quiet(f3, g1),
+            Move::quiet(f6, g8),
+        ];
+
+        assert_eq!(board.repetitions(), 0);
+
+        for chess_move in shuffle {
+            movegen.make_move_unchecked(&mut board, chess_move);
+        }
+
+        assert_eq!(board.repetitions(), 1);
+        assert!(board.is_repetition());
+        assert!(!board.is_threefold_repetition());
+
+        for chess_move in shuffle {
+            movegen.make_move_unchecked(&mut board, chess_move);
+        }
+
+        assert_eq!(board.repetitions(), 2);
+        assert!(board.is_threefold_repetition());
+
+        for chess_move in shuffle.iter().rev() {
+            movegen.unmake_move(&mut board, *chess_move);
+        }
+
+        assert_eq!(board.repetitions(), 1);
+    }
+
+    #[test]
+    fn repetitions_after_irreversible_move() {
+        let mut board = Board::from_fen(b"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        let movegen = MoveGenerator::new();
+
+        // NOTE: kings return to the same squares twice,
+        //       but positions before pawn move aren't the same
+        for chess_move in [
+            Move::quiet(e1, d1),
+            Move::quiet(e8, d8),
+            Move::quiet(d1, e1),
+            Move::quiet(d8, e8),
+            Move::quiet(e2, e3),
+            Move::quiet(e8, d8),
+            Move::quiet(e1, d1),
+            Move::quiet(d8, e8),
+            Move::quiet(d1, e1),
+        ] {
+            movegen.make_move_unchecked(&mut board, chess_move);
+        }
+
+        assert_eq!(board.halfmove_clock(), 4);
+        assert_eq!(board.repetitions(), 1);
+        assert!(!board.is_threefold_repetition());
+    }
+
+    #[test]
+    fn repetitions_survive_reset_ply() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let movegen = MoveGenerator::new();
+
+        for _ in 0..2 {
+            for chess_move in [
+                Move::quiet(b1, c3),
+                Move::quiet(b8, c6),
+                Move::quiet(c3, b1),
+                Move::quiet(c6, b8),
+            ] {
+                movegen.make_move_unchecked(&mut board, chess_move);
+                board.reset_ply();
+            }
+        }
+
+        assert!(board.is_threefold_repetition());
+    }
+
     #[test]
     fn rand() {
         let mut rng = FastRng::from_system_time();
@@ -808,6 +1674,72 @@ mod tests {
 
         assert!(got_white);
     }
+
+    fn assert_bitboards_in_sync(board: &Board) {
+        let mut occupied = BitboardEmpty;
+
+        for square in Square::iter() {
+            let piece = board.piece(square);
+
+            for other in 0..(Piece::TableSize as PieceInner) {
+                let other = Piece::from_index(other);
+                if other == PieceNone {
+                    continue;
+                }
+
+                assert_eq!(board.pieces(other).has(square), piece == other);
+            }
+
+            if piece != PieceNone {
+                assert!(board.color_pieces(piece.color()).has(square));
+                assert!(!board.color_pieces(piece.color().swapped()).has(square));
+                occupied.set(square);
+            }
+        }
+
+        assert_eq!(board.occupied(), occupied);
+    }
+
+    #[test]
+    fn bitboards_startpos() {
+        let board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        assert_bitboards_in_sync(&board);
+        assert_eq!(board.pieces(WhitePawn), Bitboard::from_rank(Rank2));
+        assert_eq!(board.pieces(BlackPawn), Bitboard::from_rank(Rank7));
+        assert_eq!(board.pieces(WhiteKing), Bitboard::from_square(e1));
+        assert_eq!(board.color_pieces(White), Bitboard::from_rank(Rank1) | Bitboard::from_rank(Rank2));
+        assert_eq!(board.occupied().count(), 32);
+    }
+
+    #[test]
+    fn bitboards_make_unmake() {
+        let movegen = MoveGenerator::new();
+        let mut buffer = MoveBuffer::new();
+
+        for fen in [
+            &b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"[..],
+            &b"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"[..],
+            &b"rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 1"[..],
+        ] {
+            let mut board = Board::from_fen(fen);
+            let before = board.clone();
+
+            buffer.reset();
+            movegen.generate(&board, &mut buffer);
+
+            for i in 0..buffer.len() {
+                let chess_move = buffer.get(i);
+
+                let _legal = movegen.make_move(&mut board, chess_move);
+                assert_bitboards_in_sync(&board);
+
+                movegen.unmake_move(&mut board, chess_move);
+                assert_eq!(board.pieces, before.pieces);
+                assert_eq!(board.colors, before.colors);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -838,6 +1770,58 @@ mod fuzz {
             assert_eq!(fen, next_fen);
         }
     }
+
+    #[test]
+    fn try_from_fen() {
+        let mut rng = FastRng::from_system_time();
+        let mut buffer = FenBuffer::new();
+
+        for i in 0..(11_010 * FUZZ_MULTIPLIER) {
+            let board = Board::rand(&mut rng);
+            buffer.reset();
+            board.fen(&mut buffer);
+            let fen = buffer.as_slice();
+
+            // NOTE: random boards are well-formed, but can be impossible
+            match Board::try_from_fen(fen) {
+                Ok(parsed) => assert_eq!(parsed, Board::from_fen(fen)),
+                Err(error) => assert!(matches!(
+                    error.kind,
+                    FenErrorKind::ImpossiblePawnStructure |
+                    FenErrorKind::ImpossibleKingsSetup |
+                    FenErrorKind::OpponentInCheck |
+                    FenErrorKind::ImpossibleEnPassantSquare |
+                    FenErrorKind::ImpossibleCastlingFlag(_)
+                )),
+            }
+
+            // NOTE: any garbage shouldn't break parser
+            let mut garbage = fen.to_vec();
+            let index = rng.rand_range_u8(0, garbage.len() as u8) as usize;
+            garbage[index] = rng.rand_range_u8(0, u8::MAX);
+            garbage.truncate(rng.rand_range_u8(index as u8, garbage.len() as u8) as usize + 1);
+
+            let _ = Board::try_from_fen(&garbage);
+        }
+    }
+
+    #[test]
+    fn bitboards() {
+        let mut rng = FastRng::from_system_time();
+
+        for i in 0..(11_010 * FUZZ_MULTIPLIER) {
+            let board = Board::rand(&mut rng);
+
+            for square in Square::iter() {
+                let piece = board.piece(square);
+                assert_eq!(board.occupied().has(square), piece != PieceNone);
+
+                if piece != PieceNone {
+                    assert!(board.pieces(piece).has(square));
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -43,6 +43,14 @@ impl<E, const SIZE: usize> StaticBuffer<E, SIZE> {
         &self.data[..self.cursor]
     }
 
+    #[inline(always)]
+    pub fn swap(&mut self, a: usize, b: usize) {
+        always!(a < self.cursor);
+        always!(b < self.cursor);
+
+        self.data.swap(a, b);
+    }
+
     #[inline(always)]
     pub fn contains(&self, expected: E) -> bool
         where E: PartialEq
@@ -99,6 +107,9 @@ mod tests {
 
         assert_eq!(buffer.as_slice(), &[99, 42]);
 
+        buffer.swap(0, 1);
+        assert_eq!(buffer.as_slice(), &[42, 99]);
+
         buffer.reset();
         assert_eq!(buffer.len(), 0);
     }
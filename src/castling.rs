@@ -62,26 +62,6 @@ impl CastlingRights {
         self.0 = 0;
     }
 
-    // TODO: rename to something like `allow_from_fen`, name other methods accordingly
-    #[inline(always)]
-    pub fn set_from_fen(&mut self, fen: u8) {
-        const FEN_TO_CASTLING: [CastlingRights; b'q' as usize + 1] = {
-            let mut xs = [CastlingRightsNone; _];
-
-            xs[b'K' as usize] = WhiteKingSide;
-            xs[b'Q' as usize] = WhiteQueenSide;
-            xs[b'k' as usize] = BlackKingSide;
-            xs[b'q' as usize] = BlackQueenSide;
-
-            xs
-        };
-
-        let castling = get_unchecked!(FEN_TO_CASTLING, fen);
-        always!(castling != CastlingRightsNone);
-
-        self.allow(castling);
-    }
-
     #[inline(always)]
     pub const fn is_allowed(self, other: Self) -> bool {
         (self.0 & other.0) == other.0
@@ -97,30 +77,36 @@ impl CastlingRights {
         self.0 &= !other.0;
     }
 
+    // Index of single right, useful for tables
     #[inline(always)]
-    pub fn fen(self, buffer: &mut FenBuffer) {
-        if self == CastlingRightsNone {
-            buffer.add(b'-');
-            return;
-        }
+    pub const fn single_index(self) -> usize {
+        always!(self.0.count_ones() == 1);
 
-        if self.is_allowed(WhiteKingSide) {
-            buffer.add(b'K');
-        }
+        self.0.trailing_zeros() as usize
+    }
 
-        if self.is_allowed(WhiteQueenSide) {
-            buffer.add(b'Q');
-        }
+    // Color of single right
+    #[inline(always)]
+    pub const fn color(self) -> Color {
+        always!(self.0.count_ones() == 1);
 
-        if self.is_allowed(BlackKingSide) {
-            buffer.add(b'k');
-        }
+        if self.0 & CastlingRightsWhite.0 != 0 { White } else { Black }
+    }
 
-        if self.is_allowed(BlackQueenSide) {
-            buffer.add(b'q');
+    // X-FEN letter of single right
+    #[inline(always)]
+    pub const fn fen(self) -> u8 {
+        match self {
+            BlackQueenSide => b'q',
+            BlackKingSide  => b'k',
+            WhiteQueenSide => b'Q',
+            WhiteKingSide  => b'K',
+            _ => never!(),
         }
     }
 
+    // NOTE: destinations are the same in Chess960,
+    //       only initial squares of king and rook differ
     #[inline(always)]
     pub const fn king_destination(self) -> Square {
         match self {
@@ -132,17 +118,6 @@ impl CastlingRights {
         }
     }
 
-    #[inline(always)]
-    pub const fn rook_initial(self) -> Square {
-        match self {
-            BlackQueenSide => a8,
-            BlackKingSide  => h8,
-            WhiteQueenSide => a1,
-            WhiteKingSide  => h1,
-            _ => never!(),
-        }
-    }
-
     #[inline(always)]
     pub const fn rook_destination(self) -> Square {
         match self {
@@ -166,3 +141,11 @@ pub const CastlingRightsBlack: CastlingRights = CastlingRights(0b0011);
 pub const CastlingRightsWhite: CastlingRights = CastlingRights(0b1100);
 
 pub const CastlingRightsAll: CastlingRights = CastlingRights(0b1111);
+
+// NOTE: order of letters in fen
+pub const CastlingRightsSingle: [CastlingRights; 4] = [
+    WhiteKingSide,
+    WhiteQueenSide,
+    BlackKingSide,
+    BlackQueenSide,
+];
@@ -11,8 +11,10 @@ pub type MoveInner = u32; // PERF: try smaller and bigger types
 // |   |     | - captured piece
 // |   |
 // |   | - promoted piece
-// | - special bit
+// | - special bit (en passant if something is captured, castling otherwise)
 // Total bits: 1 + 3 + 3 + 6 + 6 = 19
+// NOTE: castling is encoded as king captures own rook,
+//       so it's the same for chess and Chess960
 // PERF: promoted piece could be encoded in special
 //       bits to save space cause it's impossible
 //       to promote to king or pawn
@@ -21,7 +23,8 @@ pub struct Move(MoveInner);
 
 impl Move {
     pub const Mask: MoveInner             = 0b1111111111111111111;
-    pub const EnPassantSpecial: MoveInner = 0b1000000000000000000;
+    pub const SpecialMask: MoveInner      = 0b1000000000000000000;
+    pub const CapturedMask: MoveInner     = 0b0000111000000000000;
 
     // PERF: try to store Piece instead of Dignity
     #[inline(always)]
@@ -111,25 +114,11 @@ impl Move {
     }
 
     #[inline(always)]
-    pub const fn king_side_castling(
-        from: Square,
-        to: Square,
-    ) -> Self {
-        always!(from.file() == FileE);
-        always!(to.file() == FileG);
-
-        Self::new(from, to, DignityNone, DignityNone, 0)
-    }
-
-    #[inline(always)]
-    pub const fn queen_side_castling(
-        from: Square,
-        to: Square,
-    ) -> Self {
-        always!(from.file() == FileE);
-        always!(to.file() == FileC);
+    pub const fn castling(king: Square, rook: Square) -> Self {
+        always!(king.rank() == rook.rank());
+        always!(king.rank() == Rank1 || king.rank() == Rank8);
 
-        Self::new(from, to, DignityNone, DignityNone, 0)
+        Self::new(king, rook, DignityNone, DignityNone, 1)
     }
 
     #[inline(always)]
@@ -160,6 +149,13 @@ impl Move {
         Dignity::from_index(index)
     }
 
+    #[inline(always)]
+    pub const fn from_index(index: MoveInner) -> Self {
+        always!(index & Self::Mask == index);
+
+        Self(index)
+    }
+
     #[inline(always)]
     pub const fn index(self) -> MoveInner {
         self.0
@@ -170,6 +166,11 @@ impl Move {
         self.captured() != DignityNone
     }
 
+    #[inline(always)]
+    pub const fn is_promotion(self) -> bool {
+        self.promoted() != DignityNone
+    }
+
     #[inline(always)]
     pub const fn is_pawn_double_move(self, moved: Dignity) -> bool {
         if moved != Pawn {
@@ -186,22 +187,36 @@ impl Move {
     }
 
     #[inline(always)]
-    pub const fn is_king_side_castling(self, moved: Dignity) -> bool {
-        (self.from().file() == FileE) &&
-        (self.to().file() == FileG) &&
-        (moved == King)
+    pub const fn is_castling(self) -> bool {
+        self.0 & (Self::SpecialMask | Self::CapturedMask) == Self::SpecialMask
     }
 
     #[inline(always)]
-    pub const fn is_queen_side_castling(self, moved: Dignity) -> bool {
-        (self.from().file() == FileE) &&
-        (self.to().file() == FileC) &&
-        (moved == King)
+    pub const fn is_king_side_castling(self) -> bool {
+        self.is_castling() && self.to().index() > self.from().index()
+    }
+
+    #[inline(always)]
+    pub const fn is_queen_side_castling(self) -> bool {
+        self.is_castling() && self.to().index() < self.from().index()
+    }
+
+    // Castling right which is used by castling move
+    #[inline(always)]
+    pub const fn castling_rights(self) -> CastlingRights {
+        always!(self.is_castling());
+
+        let side = if self.from().rank() == Rank1 { White } else { Black };
+        if self.is_king_side_castling() {
+            CastlingRights::king_side(side)
+        } else {
+            CastlingRights::queen_side(side)
+        }
     }
 
     #[inline(always)]
     pub const fn is_en_passant(self) -> bool {
-        self.index() & Self::EnPassantSpecial != 0
+        self.0 & Self::SpecialMask != 0 && self.is_capture()
     }
 }
 
@@ -236,6 +251,43 @@ mod tests {
         assert_eq!(Pawn, chess_move.captured());
     }
 
+    #[test]
+    fn tactical_flags() {
+        assert!(Move::capture(e4, f5, Rook).is_capture());
+        assert!(!Move::capture(e4, f5, Rook).is_promotion());
+
+        assert!(Move::promotion(a7, a8, Queen).is_promotion());
+        assert!(!Move::promotion(a7, a8, Queen).is_capture());
+
+        assert!(Move::promotion_capture(a7, b8, Knight, Queen).is_promotion());
+        assert!(Move::promotion_capture(a7, b8, Knight, Queen).is_capture());
+
+        assert!(!Move::quiet(e2, e4).is_capture());
+        assert!(!Move::quiet(e2, e4).is_promotion());
+    }
+
+    #[test]
+    fn castling_flags() {
+        let king_side = Move::castling(e1, h1);
+        assert!(king_side.is_castling());
+        assert!(king_side.is_king_side_castling());
+        assert!(!king_side.is_queen_side_castling());
+        assert!(!king_side.is_en_passant());
+        assert!(!king_side.is_capture());
+        assert_eq!(king_side.castling_rights(), WhiteKingSide);
+
+        // NOTE: Chess960 castling, king doesn't leave b8
+        let queen_side = Move::castling(b8, a8);
+        assert!(queen_side.is_queen_side_castling());
+        assert_eq!(queen_side.castling_rights(), BlackQueenSide);
+
+        let en_passant = Move::en_passant(e5, d6);
+        assert!(en_passant.is_en_passant());
+        assert!(!en_passant.is_castling());
+
+        assert!(!Move::quiet(e1, g1).is_castling());
+    }
+
     #[test]
     fn format_promotion() {
         let chess_move = Move::promotion(a7, a8, Queen);
@@ -0,0 +1,411 @@
+use crate::prelude::*;
+
+// NOTE: values and piece-square tables are from PeSTO
+//       https://www.chessprogramming.org/PeSTO%27s_Evaluation_Function
+
+pub type Phase = i32;
+
+// NOTE: phase is 24 in starting position and 0 with pawns and kings only,
+//       it can be higher with promoted pieces, so it's clamped
+pub const MAX_PHASE: Phase = 24;
+
+// Indexed by dignity
+const PHASE_WEIGHTS: [Phase; 8] = [0, 0, 1, 1, 2, 4, 0, 0];
+
+const MIDDLEGAME_VALUES: [Score; 8] = [0, 82, 337, 365, 477, 1025, 0, 0];
+const ENDGAME_VALUES: [Score; 8] = [0, 94, 281, 297, 512, 936, 0, 0];
+
+// NOTE: tables are from white point of view and start from a8,
+//       so they look like a board from white side
+const MIDDLEGAME_TABLES: [[Score; 64]; 8] = [
+    // DignityNone
+    [0; 64],
+    // Pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         98, 134,  61,  95,  68, 126,  34, -11,
+         -6,   7,  26,  31,  65,  56,  25, -20,
+        -14,  13,   6,  21,  23,  12,  17, -23,
+        -27,  -2,  -5,  12,  17,   6,  10, -25,
+        -26,  -4,  -4, -10,   3,   3,  33, -12,
+        -35,  -1, -20, -23, -15,  24,  38, -22,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // Knight
+    [
+       -167, -89, -34, -49,  61, -97, -15,-107,
+        -73, -41,  72,  36,  23,  62,   7, -17,
+        -47,  60,  37,  65,  84, 129,  73,  44,
+         -9,  17,  19,  53,  37,  69,  18,  22,
+        -13,   4,  16,  13,  28,  19,  21,  -8,
+        -23,  -9,  12,  10,  19,  17,  25, -16,
+        -29, -53, -12,  -3,  -1,  18, -14, -19,
+       -105, -21, -58, -33, -17, -28, -19, -23,
+    ],
+    // Bishop
+    [
+        -29,   4, -82, -37, -25, -42,   7,  -8,
+        -26,  16, -18, -13,  30,  59,  18, -47,
+        -16,  37,  43,  40,  35,  50,  37,  -2,
+         -4,   5,  19,  50,  37,  37,   7,  -2,
+         -6,  13,  13,  26,  34,  12,  10,   4,
+          0,  15,  15,  15,  14,  27,  18,  10,
+          4,  15,  16,   0,   7,  21,  33,   1,
+        -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    // Rook
+    [
+         32,  42,  32,  51,  63,   9,  31,  43,
+         27,  32,  58,  62,  80,  67,  26,  44,
+         -5,  19,  26,  36,  17,  45,  61,  16,
+        -24, -11,   7,  26,  24,  35,  -8, -20,
+        -36, -26, -12,  -1,   9,  -7,   6, -23,
+        -45, -25, -16, -17,   3,   0,  -5, -33,
+        -44, -16, -20,  -9,  -1,  11,  -6, -71,
+        -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    // Queen
+    [
+        -28,   0,  29,  12,  59,  44,  43,  45,
+        -24, -39,  -5,   1, -16,  57,  28,  54,
+        -13, -17,   7,   8,  29,  56,  47,  57,
+        -27, -27, -16, -16,  -1,  17,  -2,   1,
+         -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+        -14,   2, -11,  -2,  -5,   2,  14,   5,
+        -35,  -8,  11,   2,   8,  15,  -3,   1,
+         -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    // King
+    [
+        -65,  23,  16, -15, -56, -34,   2,  13,
+         29,  -1, -20,  -7,  -8,  -4, -38, -29,
+         -9,  24,   2, -16, -20,   6,  22, -22,
+        -17, -20, -12, -27, -30, -25, -14, -36,
+        -49,  -1, -27, -39, -46, -44, -33, -51,
+        -14, -14, -22, -46, -44, -30, -15, -27,
+          1,   7,  -8, -64, -43, -16,   9,   8,
+        -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+    // Reserved - not used
+    [0; 64],
+];
+
+const ENDGAME_TABLES: [[Score; 64]; 8] = [
+    // DignityNone
+    [0; 64],
+    // Pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        178, 173, 158, 134, 147, 132, 165, 187,
+         94, 100,  85,  67,  56,  53,  82,  84,
+         32,  24,  13,   5,  -2,   4,  17,  17,
+         13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+          4,   7,  -6,   1,   0,  -5,  -1,  -8,
+         13,   8,   8,  10,  13,   0,   2,  -7,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // Knight
+    [
+        -58, -38, -13, -28, -31, -27, -63, -99,
+        -25,  -8, -25,  -2,  -9, -25, -24, -52,
+        -24, -20,  10,   9,  -1,  -9, -19, -41,
+        -17,   3,  22,  22,  22,  11,   8, -18,
+        -18,  -6,  16,  25,  16,  17,   4, -18,
+        -23,  -3,  -1,  15,  10,  -3, -20, -22,
+        -42, -20, -10,  -5,  -2, -20, -23, -44,
+        -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    // Bishop
+    [
+        -14, -21, -11,  -8,  -7,  -9, -17, -24,
+         -8,  -4,   7, -12,  -3, -13,  -4, -14,
+          2,  -8,   0,  -1,  -2,   6,   0,   4,
+         -3,   9,  12,   9,  14,  10,   3,   2,
+         -6,   3,  13,  19,   7,  10,  -3,  -9,
+        -12,  -3,   8,  10,  13,   3,  -7, -15,
+        -14, -18,  -7,  -1,   4,  -9, -15, -27,
+        -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    // Rook
+    [
+         13,  10,  18,  15,  12,  12,   8,   5,
+         11,  13,  13,  11,  -3,   3,   8,   3,
+          7,   7,   7,   5,   4,  -3,  -5,  -3,
+          4,   3,  13,   1,   2,   1,  -1,   2,
+          3,   5,   8,   4,  -5,  -6,  -8, -11,
+         -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+         -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+         -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    // Queen
+    [
+         -9,  22,  22,  27,  27,  19,  10,  20,
+        -17,  20,  32,  41,  58,  25,  30,   0,
+        -20,   6,   9,  49,  47,  35,  19,   9,
+          3,  22,  24,  45,  57,  40,  57,  36,
+        -18,  28,  19,  47,  31,  34,  39,  23,
+        -16, -27,  15,   6,   9,  17,  10,   5,
+        -22, -23, -30, -16, -16, -23, -36, -32,
+        -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    // King
+    [
+        -74, -35, -18, -18, -11,  15,   4, -17,
+        -12,  17,  14,  17,  17,  38,  23,  11,
+         10,  17,  23,  15,  20,  45,  44,  13,
+         -8,  22,  24,  27,  26,  33,  26,   3,
+        -18,  -4,  21,  24,  27,  23,   9, -11,
+        -19,  -3,  11,  21,  23,  16,   7,  -9,
+        -27, -11,   4,  13,  14,   4,  -5, -17,
+        -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+    // Reserved - not used
+    [0; 64],
+];
+
+// Combined material and piece-square values indexed by piece and square,
+// positive for white pieces and negative for black ones
+pub const PIECE_SQUARE_TO_MIDDLEGAME: [[Score; 64]; Piece::TableSize] =
+    piece_square_table(MIDDLEGAME_VALUES, MIDDLEGAME_TABLES);
+
+pub const PIECE_SQUARE_TO_ENDGAME: [[Score; 64]; Piece::TableSize] =
+    piece_square_table(ENDGAME_VALUES, ENDGAME_TABLES);
+
+pub const PIECE_TO_PHASE: [Phase; Piece::TableSize] = {
+    let mut xs = [0; Piece::TableSize];
+
+    let mut dignity = Pawn.index();
+    while dignity <= King.index() {
+        let weight = PHASE_WEIGHTS[dignity as usize];
+        xs[Piece::new(White, Dignity::from_index(dignity)).index() as usize] = weight;
+        xs[Piece::new(Black, Dignity::from_index(dignity)).index() as usize] = weight;
+
+        dignity += 1;
+    }
+
+    xs
+};
+
+const fn piece_square_table(
+    values: [Score; 8],
+    tables: [[Score; 64]; 8],
+) -> [[Score; 64]; Piece::TableSize] {
+    let mut xs = [[0; 64]; Piece::TableSize];
+
+    let mut dignity = Pawn.index();
+    while dignity <= King.index() {
+        let white = Piece::new(White, Dignity::from_index(dignity)).index() as usize;
+        let black = Piece::new(Black, Dignity::from_index(dignity)).index() as usize;
+
+        let value = values[dignity as usize];
+        let table = tables[dignity as usize];
+
+        let mut square = 0;
+        while square < 64 {
+            // NOTE: x ^ 56 flips rank of square
+            xs[white][square] = value + table[square ^ 56];
+            xs[black][square] = -(value + table[square]);
+
+            square += 1;
+        }
+
+        dignity += 1;
+    }
+
+    xs
+}
+
+#[inline(always)]
+pub const fn game_phase(board: &Board) -> Phase {
+    let phase = board.phase();
+
+    if phase < MAX_PHASE { phase } else { MAX_PHASE }
+}
+
+// Scores position from side to move point of view
+// NOTE: middlegame and endgame scores are updated incrementally by Board
+#[inline(always)]
+pub const fn evaluate(board: &Board) -> Score {
+    let phase = game_phase(board);
+    let score = (
+        board.middlegame_score() * phase +
+        board.endgame_score() * (MAX_PHASE - phase)
+    ) / MAX_PHASE;
+
+    if board.side_to_move() == White {
+        score
+    } else {
+        -score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate_fen(fen: &[u8]) -> Score {
+        evaluate(&Board::from_fen(fen))
+    }
+
+    // Returns middlegame score, endgame score and phase, computed from squares
+    fn evaluate_from_scratch(board: &Board) -> (Score, Score, Phase) {
+        let mut middlegame = 0;
+        let mut endgame = 0;
+        let mut phase = 0;
+
+        for square in Square::iter() {
+            let piece = board.piece(square);
+            if piece == PieceNone {
+                continue;
+            }
+
+            let piece_index = piece.index() as usize;
+            let square_index = square.index() as usize;
+
+            middlegame += PIECE_SQUARE_TO_MIDDLEGAME[piece_index][square_index];
+            endgame += PIECE_SQUARE_TO_ENDGAME[piece_index][square_index];
+            phase += PIECE_TO_PHASE[piece_index];
+        }
+
+        (middlegame, endgame, phase)
+    }
+
+    fn assert_incremental(board: &Board) {
+        let incremental = (board.middlegame_score(), board.endgame_score(), board.phase());
+        assert_eq!(incremental, evaluate_from_scratch(board));
+    }
+
+    #[test]
+    fn incremental_from_fen() {
+        for fen in [
+            &b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"[..],
+            &b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"[..],
+            &b"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"[..],
+            &b"4k3/8/8/8/8/8/8/4K3 w - - 0 1"[..],
+        ] {
+            assert_incremental(&Board::from_fen(fen));
+        }
+    }
+
+    #[test]
+    fn incremental_make_unmake() {
+        let movegen = MoveGenerator::new();
+        let mut buffer = MoveBuffer::new();
+
+        // NOTE: positions with captures, promotions, castling and en passant
+        for fen in [
+            &b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"[..],
+            &b"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"[..],
+            &b"rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 1"[..],
+        ] {
+            let mut board = Board::from_fen(fen);
+            let before = (board.middlegame_score(), board.endgame_score(), board.phase());
+
+            buffer.reset();
+            movegen.generate(&board, &mut buffer);
+
+            for i in 0..buffer.len() {
+                let chess_move = buffer.get(i);
+
+                let _legal = movegen.make_move(&mut board, chess_move);
+                assert_incremental(&board);
+
+                movegen.unmake_move(&mut board, chess_move);
+                assert_eq!((board.middlegame_score(), board.endgame_score(), board.phase()), before);
+            }
+        }
+    }
+
+    #[test]
+    fn startpos_is_equal() {
+        let score = evaluate_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(score, 0);
+
+        let score = evaluate_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1");
+        assert_eq!(score, 0);
+    }
+
+    #[test]
+    fn side_to_move_point_of_view() {
+        let white = evaluate_fen(b"4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let black = evaluate_fen(b"4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+
+        assert!(white > 0);
+        assert_eq!(white, -black);
+    }
+
+    #[test]
+    fn mirrored_positions() {
+        for (fen, mirrored_fen) in [
+            (
+                &b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"[..],
+                &b"r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1"[..],
+            ),
+            (
+                &b"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"[..],
+                &b"8/4p1p1/8/1r3P1K/kp5R/3P4/2P5/8 b - - 0 1"[..],
+            ),
+        ] {
+            assert_eq!(evaluate_fen(fen), evaluate_fen(mirrored_fen));
+        }
+    }
+
+    #[test]
+    fn game_phase_bounds() {
+        let board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(game_phase(&board), MAX_PHASE);
+
+        let board = Board::from_fen(b"4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1");
+        assert_eq!(game_phase(&board), 0);
+
+        let board = Board::from_fen(b"qqqqkqqq/8/8/8/8/8/8/QQQQKQQQ w - - 0 1");
+        assert_eq!(game_phase(&board), MAX_PHASE);
+    }
+
+    #[test]
+    fn endgame_uses_endgame_tables() {
+        let score = evaluate_fen(b"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+
+        let expected =
+            PIECE_SQUARE_TO_ENDGAME[WhitePawn.index() as usize][e2.index() as usize] +
+            PIECE_SQUARE_TO_ENDGAME[WhiteKing.index() as usize][e1.index() as usize] +
+            PIECE_SQUARE_TO_ENDGAME[BlackKing.index() as usize][e8.index() as usize];
+
+        assert_eq!(score, expected);
+    }
+
+    #[test]
+    fn piece_square_tables_orientation() {
+        // NOTE: pawns are more valuable closer to promotion
+        let white_pawn = PIECE_SQUARE_TO_ENDGAME[WhitePawn.index() as usize];
+        assert!(white_pawn[e7.index() as usize] > white_pawn[e2.index() as usize]);
+
+        let black_pawn = PIECE_SQUARE_TO_ENDGAME[BlackPawn.index() as usize];
+        assert!(black_pawn[e2.index() as usize] < black_pawn[e7.index() as usize]);
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use super::*;
+
+    use test::{Bencher, black_box};
+
+    #[bench]
+    fn evaluate_startpos(b: &mut Bencher) {
+        let board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        b.iter(|| {
+            evaluate(black_box(&board))
+        })
+    }
+
+    #[bench]
+    fn evaluate_kiwipete(b: &mut Bencher) {
+        let board = Board::from_fen(b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        b.iter(|| {
+            evaluate(black_box(&board))
+        })
+    }
+}
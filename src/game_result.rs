@@ -0,0 +1,148 @@
+use crate::prelude::*;
+
+// NOTE: halfmove clock counts plies, rule is about 50 moves of each side
+pub const FIFTY_MOVE_RULE_PLIES: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    Ongoing,
+    Checkmate { winner: Color },
+    Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+}
+
+impl GameResult {
+    #[inline(always)]
+    pub fn is_over(self) -> bool {
+        self != GameResult::Ongoing
+    }
+
+    #[inline(always)]
+    pub fn is_draw(self) -> bool {
+        matches!(
+            self,
+            GameResult::Stalemate |
+            GameResult::FiftyMoveRule |
+            GameResult::ThreefoldRepetition |
+            GameResult::InsufficientMaterial
+        )
+    }
+
+    #[inline(always)]
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            GameResult::Checkmate { winner } => Some(winner),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_result(fen: &[u8]) -> GameResult {
+        let board = Board::from_fen(fen);
+        let movegen = MoveGenerator::new();
+
+        movegen.game_result(&board)
+    }
+
+    #[test]
+    fn ongoing() {
+        let result = game_result(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        assert_eq!(result, GameResult::Ongoing);
+        assert!(!result.is_over());
+        assert!(!result.is_draw());
+        assert_eq!(result.winner(), None);
+    }
+
+    #[test]
+    fn checkmate() {
+        let result = game_result(b"R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+
+        assert_eq!(result, GameResult::Checkmate { winner: White });
+        assert!(result.is_over());
+        assert!(!result.is_draw());
+        assert_eq!(result.winner(), Some(White));
+
+        let result = game_result(b"rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert_eq!(result, GameResult::Checkmate { winner: Black });
+    }
+
+    #[test]
+    fn stalemate() {
+        let result = game_result(b"7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+
+        assert_eq!(result, GameResult::Stalemate);
+        assert!(result.is_draw());
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        assert_eq!(
+            game_result(b"4k3/8/8/8/8/8/8/R3K3 w - - 99 80"),
+            GameResult::Ongoing,
+        );
+
+        assert_eq!(
+            game_result(b"4k3/8/8/8/8/8/8/R3K3 w - - 100 80"),
+            GameResult::FiftyMoveRule,
+        );
+
+        // NOTE: checkmate has priority over fifty-move rule
+        assert_eq!(
+            game_result(b"R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80"),
+            GameResult::Checkmate { winner: White },
+        );
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut board = Board::from_fen(b"4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let movegen = MoveGenerator::new();
+
+        for _ in 0..2 {
+            for chess_move in [
+                Move::quiet(a1, a2),
+                Move::quiet(e8, d8),
+                Move::quiet(a2, a1),
+                Move::quiet(d8, e8),
+            ] {
+                assert_eq!(movegen.game_result(&board), GameResult::Ongoing);
+                movegen.make_move_unchecked(&mut board, chess_move);
+            }
+        }
+
+        assert_eq!(movegen.game_result(&board), GameResult::ThreefoldRepetition);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for fen in [
+            b"4k3/8/8/8/8/8/8/4K3 w - - 0 1".as_slice(),
+            b"4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            b"4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+            b"2b1k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            b"2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1",
+            b"1b2k3/8/8/8/8/8/8/B1B1K3 w - - 0 1",
+        ] {
+            assert_eq!(game_result(fen), GameResult::InsufficientMaterial);
+        }
+
+        for fen in [
+            b"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".as_slice(),
+            b"4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            b"4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+            b"4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+            b"2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            b"1n2k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            b"4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+        ] {
+            assert_eq!(game_result(fen), GameResult::Ongoing);
+        }
+    }
+}
@@ -22,34 +22,39 @@
 #![feature(test)]
 extern crate test;
 
+mod attacks;
+mod bitboard;
 mod board;
 mod buffer;
 mod castling;
 mod chess_move;
 mod color;
+mod eval;
+mod game_result;
 mod hint;
 mod move_generator;
+mod move_ordering;
+mod notation;
 mod perft;
+mod perft_suite;
 mod piece;
 mod prelude;
 mod rand;
+mod search;
 mod settings;
 mod square;
+mod time_manager;
 mod transposition_table;
+mod uci;
 mod util;
 
-#[cfg(feature = "perft")]
-fn perft() {
-    let fen = b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
-    let nodes = perft::perft_threaded(fen, 8);
-    println!("Perft is {nodes}");
-}
-
-#[cfg(not(feature = "perft"))]
-fn perft() {
-    // pass
-}
-
 fn main() {
-    perft();
+    attacks::init_attacks();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        #[cfg(feature = "perft")]
+        Some("perftsuite") => std::process::exit(perft_suite::run_cli(&args[1..])),
+        _ => uci::Uci::new().run(),
+    }
 }
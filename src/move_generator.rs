@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-const MAX_MOVE_BUFFER_SIZE: usize = 500;
+pub const MAX_MOVE_BUFFER_SIZE: usize = 500;
 
 pub type MoveBuffer = StaticBuffer<Move, MAX_MOVE_BUFFER_SIZE>;
 
@@ -41,11 +41,286 @@ impl MoveGenerator {
         }
     }
 
+    // Generates only legal moves, using check and pin masks
+    // NOTE: pseudo-legal `generate` with legality check in `make_move`
+    //       is kept for comparison
+    pub fn generate_legal(
+        &self,
+        board: &Board,
+        buffer: &mut MoveBuffer,
+    ) {
+        let stm = board.side_to_move();
+        let opp_color = stm.swapped();
+
+        let king = board.pieces(Piece::new(stm, King)).first();
+        let us = board.color_pieces(stm);
+        let them = board.color_pieces(opp_color);
+        let occupied = us | them;
+
+        // King moves
+        // NOTE: king is removed from occupancy, so it can't step back
+        //       along the ray of a slider which attacks it
+        let without_king = occupied ^ Bitboard::from_square(king);
+        for to in (king_attacks(king) & !us).iter() {
+            if self.attackers(to, without_king, board, opp_color).is_empty() {
+                self.add_moves(king, Bitboard::from_square(to), board, buffer);
+            }
+        }
+
+        let checkers = self.attackers(king, occupied, board, opp_color);
+        if checkers.has_many() {
+            return;
+        }
+
+        // Squares which capture checker or block its ray
+        let check_mask = if checkers.is_empty() {
+            BitboardFull
+        } else {
+            checkers | between(king, checkers.first())
+        };
+
+        let pinned = self.pinned(king, board, opp_color);
+        let target_mask = !us & check_mask;
+
+        // NOTE: pinned knight can't move at all
+        let knights = board.pieces(Piece::new(stm, Knight)) & !pinned;
+        for from in knights.iter() {
+            self.add_moves(from, knight_attacks(from) & target_mask, board, buffer);
+        }
+
+        let queens = board.pieces(Piece::new(stm, Queen));
+
+        let bishops = board.pieces(Piece::new(stm, Bishop)) | queens;
+        for from in bishops.iter() {
+            let mut targets = bishop_attacks(from, occupied) & target_mask;
+            if pinned.has(from) {
+                targets &= line(king, from);
+            }
+
+            self.add_moves(from, targets, board, buffer);
+        }
+
+        let rooks = board.pieces(Piece::new(stm, Rook)) | queens;
+        for from in rooks.iter() {
+            let mut targets = rook_attacks(from, occupied) & target_mask;
+            if pinned.has(from) {
+                targets &= line(king, from);
+            }
+
+            self.add_moves(from, targets, board, buffer);
+        }
+
+        let pawns = board.pieces(Piece::new(stm, Pawn));
+        for from in pawns.iter() {
+            let mut allowed = check_mask;
+            if pinned.has(from) {
+                allowed &= line(king, from);
+            }
+
+            self.generate_legal_for_pawn(from, allowed, board, buffer);
+        }
+
+        if board.en_passant_file() != FileEnPassantNone {
+            let to = Square::en_passant(stm, board.en_passant_file());
+            let captured = Bitboard::from_square(to.forward(opp_color, 1));
+
+            for from in (pawn_attacks(opp_color, to) & pawns).iter() {
+                // NOTE: en passant removes two pieces from the same rank,
+                //       so it's easier to check king safety directly
+                let occupied_after = (occupied ^ Bitboard::from_square(from) ^ captured) |
+                    Bitboard::from_square(to);
+                let attackers = self.attackers(king, occupied_after, board, opp_color) & !captured;
+
+                if attackers.is_empty() {
+                    buffer.add(Move::en_passant(from, to));
+                }
+            }
+        }
+
+        if checkers.is_empty() {
+            self.generate_legal_castling(CastlingRights::king_side(stm), king, board, buffer);
+            self.generate_legal_castling(CastlingRights::queen_side(stm), king, board, buffer);
+        }
+    }
+
+    fn generate_legal_for_pawn(
+        &self,
+        from: Square,
+        allowed: Bitboard,
+        board: &Board,
+        buffer: &mut MoveBuffer,
+    ) {
+        let stm = board.side_to_move();
+        let occupied = board.occupied();
+
+        let single = Bitboard::from_square(from).forward(stm, 1) & !occupied;
+        let double = if from.rank() == Rank::pawn_double_rank(stm) {
+            single.forward(stm, 1) & !occupied
+        } else {
+            BitboardEmpty
+        };
+
+        let them = board.color_pieces(stm.swapped());
+        let captures = pawn_attacks(stm, from) & them & allowed;
+
+        if from.rank() == Rank::pawn_pre_promotion_rank(stm) {
+            for to in (single & allowed).iter() {
+                buffer.add(Move::promotion(from, to, Knight));
+                buffer.add(Move::promotion(from, to, Bishop));
+                buffer.add(Move::promotion(from, to, Rook));
+                buffer.add(Move::promotion(from, to, Queen));
+            }
+
+            for to in captures.iter() {
+                let captured = board.piece(to).dignity();
+
+                buffer.add(Move::promotion_capture(from, to, captured, Knight));
+                buffer.add(Move::promotion_capture(from, to, captured, Bishop));
+                buffer.add(Move::promotion_capture(from, to, captured, Rook));
+                buffer.add(Move::promotion_capture(from, to, captured, Queen));
+            }
+
+            return;
+        }
+
+        for to in (single & allowed).iter() {
+            buffer.add(Move::pawn_single(from, to));
+        }
+
+        for to in (double & allowed).iter() {
+            buffer.add(Move::pawn_double(from, to));
+        }
+
+        for to in captures.iter() {
+            buffer.add(Move::capture(from, to, board.piece(to).dignity()));
+        }
+    }
+
+    // NOTE: king must not be in check here
+    fn generate_legal_castling(
+        &self,
+        cr: CastlingRights,
+        king: Square,
+        board: &Board,
+        buffer: &mut MoveBuffer,
+    ) {
+        // NOTE: king must stay on initial square, which is in castling mask
+        if !board.castling_rights().is_allowed(cr) || !board.castling_mask(king).is_allowed(cr) {
+            return;
+        }
+
+        let stm = board.side_to_move();
+        let rook = board.castling_rook(cr);
+        if board.piece(rook) != Piece::new(stm, Rook) {
+            return;
+        }
+
+        // NOTE: in Chess960 king or rook could already stay on destination
+        //       or on the path of each other, so they're not blockers
+        let king_to = cr.king_destination();
+        let rook_to = cr.rook_destination();
+        let king_path = between(king, king_to) | Bitboard::from_square(king_to);
+        let rook_path = between(rook, rook_to) | Bitboard::from_square(rook_to);
+        let king_and_rook = Bitboard::from_square(king) | Bitboard::from_square(rook);
+
+        let occupied = board.occupied();
+        if ((king_path | rook_path) & (occupied ^ king_and_rook)).is_not_empty() {
+            return;
+        }
+
+        let opp_color = stm.swapped();
+        for square in between(king, king_to).iter() {
+            if self.attackers(square, occupied, board, opp_color).is_not_empty() {
+                return;
+            }
+        }
+
+        // NOTE: castling rook could hide an attack on king destination
+        let occupied_after = (occupied ^ king_and_rook) |
+            Bitboard::from_square(king_to) |
+            Bitboard::from_square(rook_to);
+        if self.attackers(king_to, occupied_after, board, opp_color).is_not_empty() {
+            return;
+        }
+
+        buffer.add(Move::castling(king, rook));
+    }
+
+    // Pieces of `side_to_move` which attack `target` with given occupancy
+    #[inline(always)]
+    fn attackers(
+        &self,
+        target: Square,
+        occupied: Bitboard,
+        board: &Board,
+        side_to_move: Color,
+    ) -> Bitboard {
+        let pawns = board.pieces(Piece::new(side_to_move, Pawn));
+        let knights = board.pieces(Piece::new(side_to_move, Knight));
+        let bishops = board.pieces(Piece::new(side_to_move, Bishop));
+        let rooks = board.pieces(Piece::new(side_to_move, Rook));
+        let queens = board.pieces(Piece::new(side_to_move, Queen));
+        let kings = board.pieces(Piece::new(side_to_move, King));
+
+        (pawn_attacks(side_to_move.swapped(), target) & pawns) |
+        (knight_attacks(target) & knights) |
+        (bishop_attacks(target, occupied) & (bishops | queens)) |
+        (rook_attacks(target, occupied) & (rooks | queens)) |
+        (king_attacks(target) & kings)
+    }
+
+    // Pieces of side to move which can't leave a line between king
+    // and enemy slider
+    #[inline(always)]
+    fn pinned(
+        &self,
+        king: Square,
+        board: &Board,
+        opp_color: Color,
+    ) -> Bitboard {
+        let us = board.color_pieces(opp_color.swapped());
+        let them = board.color_pieces(opp_color);
+        let occupied = us | them;
+
+        let queens = board.pieces(Piece::new(opp_color, Queen));
+        let bishops = board.pieces(Piece::new(opp_color, Bishop)) | queens;
+        let rooks = board.pieces(Piece::new(opp_color, Rook)) | queens;
+
+        // NOTE: only enemy pieces block rays here, so we can see through our pieces
+        let snipers =
+            (bishop_attacks(king, them) & bishops) |
+            (rook_attacks(king, them) & rooks);
+
+        let mut result = BitboardEmpty;
+        for sniper in snipers.iter() {
+            let blockers = between(king, sniper) & occupied;
+            if blockers.is_not_empty() && !blockers.has_many() {
+                result |= blockers & us;
+            }
+        }
+
+        result
+    }
+
+    // Makes move and checks if it was legal
+    // NOTE: illegal move still must be unmade
     pub fn make_move(
         &self,
         board: &mut Board,
         chess_move: Move,
     ) -> bool {
+        self.make_move_unchecked(board, chess_move);
+
+        self.was_legal(board, chess_move)
+    }
+
+    // Makes move without legality check,
+    // so it should be used with moves from `generate_legal`
+    pub fn make_move_unchecked(
+        &self,
+        board: &mut Board,
+        chess_move: Move,
+    ) {
         let piece = if chess_move.promoted() == DignityNone {
             board.piece(chess_move.from())
         } else {
@@ -62,7 +337,19 @@ impl MoveGenerator {
             }
         };
 
-        if chess_move.captured() == Pawn
+        // NOTE: en passant file isn't copied to new ply,
+        //       so it's read before
+        board.push_undo();
+
+        if chess_move.is_castling() {
+            // NOTE: king and rook could swap squares in Chess960,
+            //       so both are removed before placing
+            let cr = chess_move.castling_rights();
+            board.remove_piece(chess_move.from());
+            board.remove_piece(chess_move.to());
+            board.set_piece(cr.king_destination(), piece);
+            board.set_piece(cr.rook_destination(), Piece::new(stm, Rook));
+        } else if chess_move.captured() == Pawn
            && piece.dignity() == Pawn
            && Some(chess_move.to()) == ep_to
         {
@@ -80,50 +367,15 @@ impl MoveGenerator {
             board.remove_piece(chess_move.to());
         }
 
-        board.set_piece(chess_move.to(), piece);
-        board.remove_piece(chess_move.from());
-
-        if chess_move.is_king_side_castling(piece.dignity()) {
-            let cr = CastlingRights::king_side(stm);
-            board.set_piece(cr.rook_destination(), Piece::new(stm, Rook));
-            board.remove_piece(cr.rook_initial());
-        } else if chess_move.is_queen_side_castling(piece.dignity()) {
-            let stm = board.side_to_move();
-            let cr = CastlingRights::queen_side(stm);
-            board.set_piece(cr.rook_destination(), Piece::new(stm, Rook));
-            board.remove_piece(cr.rook_initial());
+        if !chess_move.is_castling() {
+            board.set_piece(chess_move.to(), piece);
+            board.remove_piece(chess_move.from());
         }
 
-        board.push_undo();
-
         // Castling checks
-        if piece.dignity() == King {
-            board.disallow_castling(CastlingRights::both(stm));
-        }
-
-        if piece.dignity() == Rook &&
-           chess_move.from().file() == FileA &&
-           chess_move.from().rank() == stm.start_rank() {
-            board.disallow_castling(CastlingRights::queen_side(stm));
-        }
-
-        if piece.dignity() == Rook &&
-           chess_move.from().file() == FileH &&
-           chess_move.from().rank() == stm.start_rank() {
-            board.disallow_castling(CastlingRights::king_side(stm));
-        }
-
-        if chess_move.captured() == Rook &&
-           chess_move.to().file() == FileA &&
-           chess_move.to().rank() == opp_color.start_rank() {
-            board.disallow_castling(CastlingRights::queen_side(opp_color));
-        }
-
-        if chess_move.captured() == Rook &&
-           chess_move.to().file() == FileH &&
-           chess_move.to().rank() == opp_color.start_rank() {
-            board.disallow_castling(CastlingRights::king_side(opp_color));
-        }
+        // NOTE: masks cover initial squares of kings and castling rooks
+        board.disallow_castling(board.castling_mask(chess_move.from()));
+        board.disallow_castling(board.castling_mask(chess_move.to()));
 
         // En-passant checks
         if chess_move.is_pawn_double_move(piece.dignity()) {
@@ -133,15 +385,19 @@ impl MoveGenerator {
         }
 
         // Halfmove clock
-        if chess_move.is_capture() || piece.dignity() == Pawn {
+        // NOTE: `piece` is already promoted one for promotions
+        if chess_move.is_capture() || chess_move.is_promotion() || piece.dignity() == Pawn {
             board.reset_halfmove_clock();
         } else {
             board.increase_halfmove_clock();
         }
 
-        board.swap_side_to_move();
+        // Fullmove counter
+        if stm == Black {
+            board.increase_fullmove_counter();
+        }
 
-        self.was_legal(board, chess_move)
+        board.swap_side_to_move();
     }
 
     pub fn unmake_move(
@@ -152,6 +408,22 @@ impl MoveGenerator {
         let moved_side = board.side_to_move();
         let opp_color = moved_side.swapped();
 
+        if chess_move.is_castling() {
+            let cr = chess_move.castling_rights();
+            board.remove_piece(cr.king_destination());
+            board.remove_piece(cr.rook_destination());
+            board.set_piece(chess_move.from(), Piece::new(opp_color, King));
+            board.set_piece(chess_move.to(), Piece::new(opp_color, Rook));
+
+            if opp_color == Black {
+                board.decrease_fullmove_counter();
+            }
+
+            board.pop_undo();
+            board.swap_side_to_move();
+            return;
+        }
+
         if chess_move.promoted() != DignityNone {
             let pawn = Piece::new(opp_color, Pawn);
             board.set_piece(chess_move.from(), pawn);
@@ -160,7 +432,6 @@ impl MoveGenerator {
             board.set_piece(chess_move.from(), moved_piece);
         }
 
-        let moved_piece = board.piece(chess_move.to()); // PERF: try to get from chess_move
         board.remove_piece(chess_move.to());
 
         if chess_move.is_en_passant() {
@@ -175,20 +446,58 @@ impl MoveGenerator {
             board.set_piece(chess_move.to(), captured_piece);
         }
 
-        if chess_move.is_queen_side_castling(moved_piece.dignity()) {
-            let cr = CastlingRights::queen_side(opp_color);
-            board.set_piece(cr.rook_initial(), Piece::new(opp_color, Rook));
-            board.remove_piece(cr.rook_destination());
-        } else if chess_move.is_king_side_castling(moved_piece.dignity()) {
-            let cr = CastlingRights::king_side(opp_color);
-            board.set_piece(cr.rook_initial(), Piece::new(opp_color, Rook));
-            board.remove_piece(cr.rook_destination());
+        if opp_color == Black {
+            board.decrease_fullmove_counter();
         }
 
         board.pop_undo();
         board.swap_side_to_move();
     }
 
+    // NOTE: it generates all legal moves,
+    //       so it's too slow to call it in every search node
+    pub fn game_result(&self, board: &Board) -> GameResult {
+        let mut buffer = MoveBuffer::new();
+        self.generate_legal(board, &mut buffer);
+
+        if buffer.len() == 0 {
+            if self.is_in_check(board) {
+                return GameResult::Checkmate { winner: board.side_to_move().swapped() };
+            }
+
+            return GameResult::Stalemate;
+        }
+
+        if board.halfmove_clock() as usize >= FIFTY_MOVE_RULE_PLIES {
+            return GameResult::FiftyMoveRule;
+        }
+
+        if board.is_threefold_repetition() {
+            return GameResult::ThreefoldRepetition;
+        }
+
+        if board.has_insufficient_material() {
+            return GameResult::InsufficientMaterial;
+        }
+
+        GameResult::Ongoing
+    }
+
+    // Enemy pieces which attack king of side to move
+    pub fn checkers(&self, board: &Board) -> Bitboard {
+        let stm = board.side_to_move();
+        let king = board.pieces(Piece::new(stm, King)).first();
+
+        self.attackers(king, board.occupied(), board, stm.swapped())
+    }
+
+    pub fn is_in_check(&self, board: &Board) -> bool {
+        let stm = board.side_to_move();
+        let king_pos = unwrap_unchecked!(board.find_king(stm));
+
+        self.can_be_attacked(king_pos, board, stm.swapped())
+    }
+
     fn was_legal(
         &self,
         board: &mut Board,
@@ -204,40 +513,60 @@ impl MoveGenerator {
             return false;
         }
 
-        let moved_piece = board.piece(chess_move.to());
-        always!(moved_piece.color() == moved_side);
-
-        if chess_move.is_king_side_castling(moved_piece.dignity()) {
-            let leave_square = unwrap_unchecked!(king_pos.by(-2, 0));
-            if self.can_be_attacked(leave_square, board, stm) {
-                return false;
+        if chess_move.is_castling() {
+            // NOTE: king must not leave check or cross attacked squares
+            let crossed = between(chess_move.from(), king_pos) | Bitboard::from_square(chess_move.from());
+            for square in (crossed & !Bitboard::from_square(king_pos)).iter() {
+                if self.can_be_attacked(square, board, stm) {
+                    return false;
+                }
             }
+        }
 
-            let cross_square = unwrap_unchecked!(king_pos.by(-1, 0));
-            if self.can_be_attacked(cross_square, board, stm) {
-                return false;
-            }
+        true
+    }
+
+    #[cfg(not(feature = "ray_attacks"))]
+    fn can_be_attacked(
+        &self,
+        target: Square,
+        board: &Board,
+        side_to_move: Color,
+    ) -> bool {
+        let target_side = side_to_move.swapped();
+        let occupied = board.occupied();
+
+        let pawns = board.pieces(Piece::new(side_to_move, Pawn));
+        if (pawn_attacks(target_side, target) & pawns).is_not_empty() {
+            return true;
         }
 
-        if chess_move.is_queen_side_castling(moved_piece.dignity()) {
-            let leave_square = unwrap_unchecked!(king_pos.by(2, 0));
-            if self.can_be_attacked(leave_square, board, stm) {
-                return false;
-            }
+        let knights = board.pieces(Piece::new(side_to_move, Knight));
+        if (knight_attacks(target) & knights).is_not_empty() {
+            return true;
+        }
 
-            let cross_square = unwrap_unchecked!(king_pos.by(1, 0));
-            if self.can_be_attacked(cross_square, board, stm) {
-                return false;
-            }
+        let queens = board.pieces(Piece::new(side_to_move, Queen));
+
+        let bishops = board.pieces(Piece::new(side_to_move, Bishop));
+        if (bishop_attacks(target, occupied) & (bishops | queens)).is_not_empty() {
+            return true;
         }
 
-        true
+        let rooks = board.pieces(Piece::new(side_to_move, Rook));
+        if (rook_attacks(target, occupied) & (rooks | queens)).is_not_empty() {
+            return true;
+        }
+
+        let kings = board.pieces(Piece::new(side_to_move, King));
+        (king_attacks(target) & kings).is_not_empty()
     }
 
+    #[cfg(feature = "ray_attacks")]
     fn can_be_attacked(
         &self,
         target: Square,
-        board: &mut Board,
+        board: &Board,
         side_to_move: Color,
     ) -> bool {
         let pawn = Piece::new(side_to_move, Pawn);
@@ -506,6 +835,75 @@ impl MoveGenerator {
         }
     }
 
+    #[cfg(not(feature = "ray_attacks"))]
+    fn generate_for_bishop(
+        &self,
+        from: Square,
+        board: &Board,
+        buffer: &mut MoveBuffer,
+    ) {
+        let targets = bishop_attacks(from, board.occupied());
+        self.generate_for_slider(from, targets, board, buffer);
+    }
+
+    #[cfg(not(feature = "ray_attacks"))]
+    fn generate_for_rook(
+        &self,
+        from: Square,
+        board: &Board,
+        buffer: &mut MoveBuffer,
+    ) {
+        let targets = rook_attacks(from, board.occupied());
+        self.generate_for_slider(from, targets, board, buffer);
+    }
+
+    #[cfg(not(feature = "ray_attacks"))]
+    fn generate_for_queen(
+        &self,
+        from: Square,
+        board: &Board,
+        buffer: &mut MoveBuffer,
+    ) {
+        let targets = queen_attacks(from, board.occupied());
+        self.generate_for_slider(from, targets, board, buffer);
+    }
+
+    #[cfg(not(feature = "ray_attacks"))]
+    #[inline(always)]
+    fn generate_for_slider(
+        &self,
+        from: Square,
+        targets: Bitboard,
+        board: &Board,
+        buffer: &mut MoveBuffer,
+    ) {
+        let stm = board.side_to_move();
+        let targets = targets & !board.color_pieces(stm);
+
+        self.add_moves(from, targets, board, buffer);
+    }
+
+    // Adds quiet moves and captures to every target square
+    // NOTE: targets must not contain pieces of side to move
+    #[inline(always)]
+    fn add_moves(
+        &self,
+        from: Square,
+        targets: Bitboard,
+        board: &Board,
+        buffer: &mut MoveBuffer,
+    ) {
+        for to in targets.iter() {
+            let dest = board.piece(to);
+            if dest == PieceNone {
+                buffer.add(Move::quiet(from, to));
+            } else {
+                buffer.add(Move::capture(from, to, dest.dignity()));
+            }
+        }
+    }
+
+    #[cfg(feature = "ray_attacks")]
     fn generate_for_bishop(
         &self,
         from: Square,
@@ -537,6 +935,7 @@ impl MoveGenerator {
         }
     }
 
+    #[cfg(feature = "ray_attacks")]
     fn generate_for_rook(
         &self,
         from: Square,
@@ -568,6 +967,7 @@ impl MoveGenerator {
         }
     }
 
+    #[cfg(feature = "ray_attacks")]
     fn generate_for_queen(
         &self,
         from: Square,
@@ -636,79 +1036,46 @@ impl MoveGenerator {
         }
 
         let stm = board.side_to_move();
-        if from == Square::king_initial(stm) {
-            self.generate_king_castling(board, buffer);
-            self.generate_queen_castling(board, buffer);
-        }
+        self.generate_castling(CastlingRights::king_side(stm), from, board, buffer);
+        self.generate_castling(CastlingRights::queen_side(stm), from, board, buffer);
     }
 
-    fn generate_king_castling(
+    // NOTE: attacked squares are checked in `was_legal`
+    fn generate_castling(
         &self,
+        cr: CastlingRights,
+        king: Square,
         board: &Board,
         buffer: &mut MoveBuffer,
     ) {
-        let stm = board.side_to_move();
-        let cr = CastlingRights::king_side(stm);
-        if !board.castling_rights().is_allowed(cr) {
-            return;
-        }
-
-        let to = cr.king_destination();
-        if board.piece(to) != PieceNone {
-            return;
-        }
-
-        let rook_from = cr.rook_initial();
-        if board.piece(rook_from) != Piece::new(stm, Rook) {
-            return;
-        }
-
-        let rook_to = cr.rook_destination();
-        if board.piece(rook_to) != PieceNone {
+        // NOTE: king must stay on initial square, which is in castling mask
+        if !board.castling_rights().is_allowed(cr) || !board.castling_mask(king).is_allowed(cr) {
             return;
         }
 
-        buffer.add(Move::king_side_castling(Square::king_initial(stm), to));
-    }
-
-    fn generate_queen_castling(
-        &self,
-        board: &Board,
-        buffer: &mut MoveBuffer,
-    ) {
         let stm = board.side_to_move();
-        let cr = CastlingRights::queen_side(stm);
-        if !board.castling_rights().is_allowed(cr) {
-            return;
-        }
-
-        let to = cr.king_destination();
-        if board.piece(to) != PieceNone {
-            return;
-        }
-
-        let rook_from = cr.rook_initial();
-        if board.piece(rook_from) != Piece::new(stm, Rook) {
-            return;
-        }
-
-        let next_to_rook = unwrap_unchecked!(rook_from.by(1, 0));
-
-        if board.piece(next_to_rook) != PieceNone {
+        let rook = board.castling_rook(cr);
+        if board.piece(rook) != Piece::new(stm, Rook) {
             return;
         }
 
+        let king_to = cr.king_destination();
         let rook_to = cr.rook_destination();
-        if board.piece(rook_to) != PieceNone {
+        let path =
+            between(king, king_to) | Bitboard::from_square(king_to) |
+            between(rook, rook_to) | Bitboard::from_square(rook_to);
+        let king_and_rook = Bitboard::from_square(king) | Bitboard::from_square(rook);
+
+        if (path & (board.occupied() ^ king_and_rook)).is_not_empty() {
             return;
         }
 
-        buffer.add(Move::queen_side_castling(Square::king_initial(stm), to));
+        buffer.add(Move::castling(king, rook));
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(super) mod tests {
     use super::*;
 
     fn generate(fen: &[u8]) -> MoveBuffer {
@@ -850,24 +1217,24 @@ mod tests {
     fn king_side_castling() {
         let buffer = generate(b"8/8/8/8/8/8/PPPPPPPP/RNBQK2R w KQkq - 0 1");
 
-        assert!(buffer.contains(Move::king_side_castling(e1, g1)));
+        assert!(buffer.contains(Move::castling(e1, h1)));
         assert_eq!(buffer.len(), 22);
 
         let buffer = generate(b"8/8/8/8/8/8/PPPPPPPP/RNBQK2R w Qkq - 0 1");
 
-        assert!(!buffer.contains(Move::king_side_castling(e1, g1)));
+        assert!(!buffer.contains(Move::castling(e1, h1)));
         assert_eq!(buffer.len(), 21);
 
         let buffer = generate(b"8/8/8/8/8/8/PPPPPPPP/RNBQK3 w KQkq - 0 1");
 
-        assert!(!buffer.contains(Move::king_side_castling(e1, g1)));
+        assert!(!buffer.contains(Move::castling(e1, h1)));
         assert_eq!(buffer.len(), 19);
     }
 
     #[test]
     fn queen_side_castling() {
         let buffer = generate(b"8/8/8/8/8/8/PPPPPPPP/R3KBNR w KQkq - 0 1");
-        assert!(buffer.contains(Move::queen_side_castling(e1, c1)));
+        assert!(buffer.contains(Move::castling(e1, a1)));
 
         for fen_no_castling in [
             &b"8/8/8/8/8/8/PPPPPPPP/R3KBNR w Kkq - 0 1"[..],
@@ -877,7 +1244,7 @@ mod tests {
             &b"8/8/8/8/8/8/PPPPPPPP/R2QKBNR w KQkq - 0 1"[..],
         ] {
             let buffer = generate(fen_no_castling);
-            assert!(!buffer.contains(Move::queen_side_castling(e1, c1)));
+            assert!(!buffer.contains(Move::castling(e1, a1)));
         }
     }
 
@@ -1042,7 +1409,7 @@ mod tests {
     fn make_move_king_side_castling() {
         let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQK2R w KQkq - 0 1");
         let movegen = MoveGenerator::new();
-        let legal = movegen.make_move(&mut board, Move::king_side_castling(e1, g1));
+        let legal = movegen.make_move(&mut board, Move::castling(e1, h1));
 
         assert!(legal);
         assert_eq!(board.piece(e1), PieceNone);
@@ -1056,7 +1423,7 @@ mod tests {
     fn make_move_queen_side_castling() {
         let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3KBNR w KQkq - 0 1");
         let movegen = MoveGenerator::new();
-        let legal = movegen.make_move(&mut board, Move::queen_side_castling(e1, c1));
+        let legal = movegen.make_move(&mut board, Move::castling(e1, a1));
 
         assert!(legal);
         assert_eq!(board.piece(e1), PieceNone);
@@ -1066,6 +1433,24 @@ mod tests {
         assert_eq!(board.piece(d1), WhiteRook);
     }
 
+    #[test]
+    fn make_move_chess960_castling() {
+        // NOTE: king and rook swap squares here
+        let mut board = Board::from_fen(b"4k3/8/8/8/8/8/8/5KR1 w G - 0 1");
+        let movegen = MoveGenerator::new();
+        let legal = movegen.make_move(&mut board, Move::castling(f1, g1));
+
+        assert!(legal);
+        assert_eq!(board.piece(g1), WhiteKing);
+        assert_eq!(board.piece(f1), WhiteRook);
+        assert_eq!(board.castling_rights(), CastlingRightsNone);
+
+        movegen.unmake_move(&mut board, Move::castling(f1, g1));
+        assert_eq!(board.piece(f1), WhiteKing);
+        assert_eq!(board.piece(g1), WhiteRook);
+        assert_eq!(board.castling_rights(), WhiteKingSide);
+    }
+
     #[test]
     fn make_move_en_passant() {
         let mut board = Board::from_fen(b"k7/8/8/5Pp1/8/8/8/K7 w - g6 0 1");
@@ -1111,21 +1496,21 @@ mod tests {
         // Leave
         let mut board = Board::from_fen(b"4r3/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
         let movegen = MoveGenerator::new();
-        let legal = movegen.make_move(&mut board, Move::king_side_castling(e1, g1));
+        let legal = movegen.make_move(&mut board, Move::castling(e1, h1));
 
         assert!(!legal);
 
         // Cross
         let mut board = Board::from_fen(b"5r2/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
         let movegen = MoveGenerator::new();
-        let legal = movegen.make_move(&mut board, Move::king_side_castling(e1, g1));
+        let legal = movegen.make_move(&mut board, Move::castling(e1, h1));
 
         assert!(!legal);
 
         // End-up
         let mut board = Board::from_fen(b"6r1/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
         let movegen = MoveGenerator::new();
-        let legal = movegen.make_move(&mut board, Move::king_side_castling(e1, g1));
+        let legal = movegen.make_move(&mut board, Move::castling(e1, h1));
 
         assert!(!legal);
     }
@@ -1135,21 +1520,21 @@ mod tests {
         // Leave
         let mut board = Board::from_fen(b"4r3/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
         let movegen = MoveGenerator::new();
-        let legal = movegen.make_move(&mut board, Move::queen_side_castling(e1, c1));
+        let legal = movegen.make_move(&mut board, Move::castling(e1, a1));
 
         assert!(!legal);
 
         // Cross
         let mut board = Board::from_fen(b"3r4/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
         let movegen = MoveGenerator::new();
-        let legal = movegen.make_move(&mut board, Move::queen_side_castling(e1, c1));
+        let legal = movegen.make_move(&mut board, Move::castling(e1, a1));
 
         assert!(!legal);
 
         // End-up
         let mut board = Board::from_fen(b"2r5/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
         let movegen = MoveGenerator::new();
-        let legal = movegen.make_move(&mut board, Move::queen_side_castling(e1, c1));
+        let legal = movegen.make_move(&mut board, Move::castling(e1, a1));
 
         assert!(!legal);
     }
@@ -1157,7 +1542,7 @@ mod tests {
     #[test]
     fn make_move_king_move_resets_castling_rights() {
         let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1");
-        let chess_move = Move::queen_side_castling(e1, c1);
+        let chess_move = Move::castling(e1, a1);
 
         let movegen = MoveGenerator::new();
         let _legal = movegen.make_move(&mut board, chess_move);
@@ -1245,6 +1630,164 @@ mod tests {
         assert_eq!(board.halfmove_clock(), 16);
     }
 
+    #[test]
+    fn is_in_check() {
+        let movegen = MoveGenerator::new();
+
+        for (fen, expected) in [
+            (&b"4k3/8/8/8/8/8/8/4K3 w - - 0 1"[..], false),
+            (&b"4k3/8/8/8/8/8/8/R3K3 b - - 0 1"[..], false),
+            (&b"4k3/8/8/8/8/8/8/4K2r w - - 0 1"[..], true),
+            (&b"4k3/8/8/1B6/8/8/8/4K3 b - - 0 1"[..], true),
+            (&b"4k3/8/3N4/8/8/8/8/4K3 b - - 0 1"[..], true),
+            (&b"4k3/8/8/8/8/8/3p4/4K3 w - - 0 1"[..], true),
+        ] {
+            let board = Board::from_fen(fen);
+            assert_eq!(movegen.is_in_check(&board), expected);
+        }
+    }
+
+    fn generate_legal(fen: &[u8]) -> MoveBuffer {
+        let board = Board::from_fen(fen);
+        let movegen = MoveGenerator::new();
+        let mut buffer = MoveBuffer::new();
+        movegen.generate_legal(&board, &mut buffer);
+
+        buffer
+    }
+
+    // Pseudo-legal moves filtered by make_move, sorted for comparison
+    pub fn pseudo_legal_moves(board: &mut Board) -> Vec<MoveInner> {
+        let movegen = MoveGenerator::new();
+        let mut buffer = MoveBuffer::new();
+        movegen.generate(board, &mut buffer);
+
+        let mut result = Vec::new();
+        for i in 0..buffer.len() {
+            let chess_move = buffer.get(i);
+            if movegen.make_move(board, chess_move) {
+                result.push(chess_move.index());
+            }
+
+            movegen.unmake_move(board, chess_move);
+        }
+
+        result.sort();
+        result
+    }
+
+    pub fn legal_moves(board: &Board) -> Vec<MoveInner> {
+        let movegen = MoveGenerator::new();
+        let mut buffer = MoveBuffer::new();
+        movegen.generate_legal(board, &mut buffer);
+
+        let mut result: Vec<_> = buffer.as_slice().iter().map(|m| m.index()).collect();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn generate_legal_startpos() {
+        let buffer = generate_legal(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        assert_eq!(buffer.len(), 20);
+    }
+
+    #[test]
+    fn generate_legal_pinned_pieces() {
+        // Knight is pinned, bishop can move along the pin only
+        let buffer = generate_legal(b"4k3/4r3/8/8/1b2N3/8/3B4/4K3 w - - 0 1");
+
+        assert!(!buffer.as_slice().iter().any(|m| m.from() == e4));
+        assert!(!buffer.contains(Move::quiet(d2, e3)));
+        assert!(buffer.contains(Move::quiet(d2, c3)));
+        assert!(buffer.contains(Move::capture(d2, b4, Bishop)));
+    }
+
+    #[test]
+    fn generate_legal_check_evasions() {
+        // Block, capture or move the king
+        let buffer = generate_legal(b"4k3/8/8/8/4r3/8/3B4/R3K3 w - - 0 1");
+
+        assert!(buffer.contains(Move::quiet(d2, e3)));
+        assert!(!buffer.contains(Move::quiet(d2, c3)));
+        assert!(!buffer.contains(Move::quiet(a1, a8)));
+        assert!(buffer.contains(Move::quiet(e1, d1)));
+        assert!(!buffer.contains(Move::quiet(e1, e2)));
+        assert!(!buffer.contains(Move::castling(e1, a1)));
+
+        // Double check allows king moves only
+        let buffer = generate_legal(b"4k3/8/8/8/4r3/3n4/8/R3K3 w Q - 0 1");
+
+        assert!(buffer.as_slice().iter().all(|m| m.from() == e1));
+    }
+
+    #[test]
+    fn generate_legal_en_passant() {
+        // Horizontal pin through both pawns
+        let buffer = generate_legal(b"4k3/8/8/2KPp2q/8/8/8/8 w - e6 0 1");
+        assert!(!buffer.contains(Move::en_passant(d5, e6)));
+
+        // Captures checking pawn
+        let buffer = generate_legal(b"8/8/8/3Pp3/5K2/8/8/4k3 w - e6 0 1");
+        assert!(buffer.contains(Move::en_passant(d5, e6)));
+    }
+
+    #[test]
+    fn generate_legal_castling() {
+        for (fen, king_side, queen_side) in [
+            (&b"4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1"[..], true, true),
+            (&b"4k3/8/8/8/8/8/8/R3K2R w - - 0 1"[..], false, false),
+            (&b"4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1"[..], false, false),
+            (&b"3r2k1/8/8/8/8/8/8/R3K2R w KQ - 0 1"[..], true, false),
+            (&b"6r1/7k/8/8/8/8/8/R3K2R w KQ - 0 1"[..], false, true),
+            (&b"1r4k1/8/8/8/8/8/8/R3K2R w KQ - 0 1"[..], true, true),
+            (&b"4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1"[..], true, false),
+        ] {
+            let buffer = generate_legal(fen);
+
+            assert_eq!(buffer.contains(Move::castling(e1, h1)), king_side);
+            assert_eq!(buffer.contains(Move::castling(e1, a1)), queen_side);
+        }
+    }
+
+    #[test]
+    fn generate_legal_chess960_castling() {
+        for (fen, chess_move, expected) in [
+            (&b"1k6/8/8/8/8/8/8/RK6 w A - 0 1"[..], Move::castling(b1, a1), true),
+            (b"6k1/8/8/8/8/8/8/6KR w H - 0 1", Move::castling(g1, h1), true),
+            (b"6k1/8/8/8/8/8/8/5KR1 w G - 0 1", Move::castling(f1, g1), true),
+            (b"6k1/8/8/8/8/8/8/1RN2K2 w B - 0 1", Move::castling(f1, b1), false),
+            (b"6k1/8/8/8/8/8/8/1R3KN1 w B - 0 1", Move::castling(f1, b1), true),
+
+            // NOTE: castling rook hides an attack on king destination
+            (b"1k6/8/8/8/8/8/8/rRK5 w B - 0 1", Move::castling(c1, b1), false),
+        ] {
+            let buffer = generate_legal(fen);
+
+            assert_eq!(buffer.contains(chess_move), expected, "{}", String::from_utf8_lossy(fen));
+        }
+    }
+
+    #[test]
+    fn generate_legal_matches_pseudo_legal() {
+        for fen in [
+            &b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"[..],
+            &b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"[..],
+            &b"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"[..],
+            &b"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"[..],
+            &b"rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"[..],
+            &b"r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"[..],
+            &b"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"[..],
+            &b"1k6/8/8/8/8/8/8/rRK5 w B - 0 1"[..],
+            &b"6k1/8/8/8/8/8/8/5KR1 w G - 0 1"[..],
+        ] {
+            let mut board = Board::from_fen(fen);
+
+            assert_eq!(legal_moves(&board), pseudo_legal_moves(&mut board));
+        }
+    }
+
     #[test]
     fn unmake_move_restores_side_to_move() {
         let mut board = Board::from_fen(b"4k3/8/8/8/8/8/8/R3K3 w KQkq - 0 1");
@@ -1302,7 +1845,7 @@ mod tests {
     #[test]
     fn unmake_move_castling_moves_rook() {
         let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1");
-        let chess_move = Move::queen_side_castling(e1, c1);
+        let chess_move = Move::castling(e1, a1);
 
         let movegen = MoveGenerator::new();
         let legal = movegen.make_move(&mut board, chess_move);
@@ -1315,7 +1858,7 @@ mod tests {
         assert_eq!(board.piece(c1), PieceNone);
         assert_eq!(board.piece(d1), PieceNone);
 
-        let chess_move = Move::king_side_castling(e1, g1);
+        let chess_move = Move::castling(e1, h1);
         let legal = movegen.make_move(&mut board, chess_move);
 
         assert!(legal);
@@ -1330,7 +1873,7 @@ mod tests {
     #[test]
     fn unmake_move_restores_castling_rights() {
         let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1");
-        let chess_move = Move::queen_side_castling(e1, c1);
+        let chess_move = Move::castling(e1, a1);
 
         let movegen = MoveGenerator::new();
         let _legal = movegen.make_move(&mut board, chess_move);
@@ -1373,6 +1916,35 @@ mod tests {
         assert_eq!(board.halfmove_clock(), 13);
     }
 
+    #[test]
+    fn make_move_promotion_resets_halfmove_clock() {
+        let mut board = Board::from_fen(b"4k3/1P6/8/8/8/8/8/4K3 w - - 12 40");
+        let movegen = MoveGenerator::new();
+        movegen.make_move_unchecked(&mut board, Move::promotion(b7, b8, Queen));
+
+        assert_eq!(board.halfmove_clock(), 0);
+    }
+
+    #[test]
+    fn make_move_increases_fullmove_counter() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 7");
+        let movegen = MoveGenerator::new();
+
+        let white_move = Move::quiet(g1, f3);
+        movegen.make_move_unchecked(&mut board, white_move);
+        assert_eq!(board.fullmove_counter(), 7);
+
+        let black_move = Move::quiet(g8, f6);
+        movegen.make_move_unchecked(&mut board, black_move);
+        assert_eq!(board.fullmove_counter(), 8);
+
+        movegen.unmake_move(&mut board, black_move);
+        assert_eq!(board.fullmove_counter(), 7);
+
+        movegen.unmake_move(&mut board, white_move);
+        assert_eq!(board.fullmove_counter(), 7);
+    }
+
     #[test]
     fn unmake_move_capture_restores_hash() {
         let mut board = Board::from_fen(b"4k3/8/8/8/8/8/8/R1b1K3 w KQkq - 0 1");
@@ -1446,6 +2018,29 @@ mod fuzz {
             }
         }
     }
+
+    #[test]
+    fn generate_legal() {
+        let mut rng = FastRng::from_system_time();
+
+        for i in 0..(11_010 * FUZZ_MULTIPLIER) {
+            let mut board = Board::rand(&mut rng);
+
+            if !board.has_possible_pawn_structure() ||
+               !board.has_possible_en_passant_square() ||
+               !board.has_possible_kings_setup()
+            {
+                continue;
+            }
+
+            assert_eq!(
+                tests::legal_moves(&board),
+                tests::pseudo_legal_moves(&mut board),
+                "{}",
+                board.debug_fen(),
+            );
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1500,6 +2095,36 @@ mod bench {
         }
     }
 
+    macro_rules! bench_generate_legal {
+        ($b:ident, $fen:literal) => {
+            let fen = black_box($fen);
+            let board = Board::from_fen(fen);
+            let movegen = MoveGenerator::new();
+
+            $b.iter(|| {
+                let mut buffer = MoveBuffer::new();
+                movegen.generate_legal(&board, &mut buffer);
+
+                buffer
+            })
+        }
+    }
+
+    #[bench]
+    fn generate_legal_startpos(b: &mut Bencher) {
+        bench_generate_legal!(b, b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    }
+
+    #[bench]
+    fn generate_legal_kiwipete(b: &mut Bencher) {
+        bench_generate_legal!(b, b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    }
+
+    #[bench]
+    fn generate_kiwipete(b: &mut Bencher) {
+        bench_generate!(b, b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    }
+
     #[bench]
     fn generate_empty(b: &mut Bencher) {
         bench_generate!(b, b"8/8/8/8/8/8/8/8 w KQkq - 0 1");
@@ -1612,7 +2237,7 @@ mod bench {
         bench_make_move!(
             b,
             b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3KBNR w KQkq - 0 1",
-            Move::queen_side_castling(e1, c1)
+            Move::castling(e1, a1)
         );
     }
 
@@ -1657,7 +2282,7 @@ mod bench {
         bench_unmake_move!(
             b,
             b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3KBNR w KQkq - 0 1",
-            Move::queen_side_castling(e1, c1)
+            Move::castling(e1, a1)
         );
     }
 
@@ -0,0 +1,304 @@
+use crate::prelude::*;
+
+pub type MoveScore = i32;
+
+// NOTE: scores are parallel to moves in `MoveBuffer`
+pub type MoveScores = StaticBuffer<MoveScore, MAX_MOVE_BUFFER_SIZE>;
+
+// NOTE: ranges of move kinds don't overlap,
+//       so kind is more important than score inside of it
+const HASH_MOVE_SCORE: MoveScore = 1 << 30;
+const TACTICAL_MOVE_SCORE: MoveScore = 1 << 29;
+const KILLER_MOVE_SCORE: MoveScore = 1 << 28;
+const COUNTERMOVE_SCORE: MoveScore = 1 << 27;
+
+// NOTE: history scores stay in (-MAX_HISTORY, MAX_HISTORY),
+//       cause every update pulls them to zero proportionally
+const MAX_HISTORY: MoveScore = 1 << 14;
+
+const KILLERS_PER_PLY: usize = 2;
+
+// Tables of quiet moves which caused beta cutoffs:
+// - killers: by ply, they're often good in sibling nodes
+// - countermoves: by previous move, refutation of it
+// - history (butterfly one): by side, from and to squares
+pub struct MoveOrdering {
+    killers: Vec<[Option<Move>; KILLERS_PER_PLY]>,
+    countermoves: Vec<[Option<Move>; 64]>,
+    history: Vec<[[MoveScore; 64]; 64]>,
+}
+
+impl MoveOrdering {
+    pub fn new() -> Self {
+        Self {
+            killers: vec![[None; KILLERS_PER_PLY]; MAX_SEARCH_DEPTH],
+            countermoves: vec![[None; 64]; 64],
+            history: vec![[[0; 64]; 64]; 2],
+        }
+    }
+
+    // Forgets everything learned in previous searches
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    // Killers are position specific, so they're dropped,
+    // history is only aged to keep it fresh
+    pub fn new_search(&mut self) {
+        self.killers.fill([None; KILLERS_PER_PLY]);
+
+        for scores in self.history.iter_mut().flatten().flatten() {
+            *scores /= 2;
+        }
+    }
+
+    #[inline(always)]
+    pub fn score(
+        &self,
+        board: &Board,
+        chess_move: Move,
+        ply: usize,
+        hash_move: Option<Move>,
+        previous_move: Option<Move>,
+    ) -> MoveScore {
+        if hash_move == Some(chess_move) {
+            return HASH_MOVE_SCORE;
+        }
+
+        if is_tactical(chess_move) {
+            return TACTICAL_MOVE_SCORE + mvv_lva(board, chess_move);
+        }
+
+        let killers = get_unchecked!(self.killers, ply);
+        if let Some(index) = killers.iter().position(|&killer| killer == Some(chess_move)) {
+            return KILLER_MOVE_SCORE + (KILLERS_PER_PLY - index) as MoveScore;
+        }
+
+        if let Some(previous_move) = previous_move {
+            if self.countermove(previous_move) == Some(chess_move) {
+                return COUNTERMOVE_SCORE;
+            }
+        }
+
+        self.history(board.side_to_move(), chess_move)
+    }
+
+    // NOTE: quiet moves aren't searched in quiescence,
+    //       so they get the lowest score possible
+    #[inline(always)]
+    pub fn score_tactical(&self, board: &Board, chess_move: Move) -> MoveScore {
+        if !is_tactical(chess_move) {
+            return MoveScore::MIN;
+        }
+
+        TACTICAL_MOVE_SCORE + mvv_lva(board, chess_move)
+    }
+
+    // Should be called when quiet move causes beta cutoff,
+    // `tried_moves` are ones searched before it in the same node
+    pub fn update_on_cutoff(
+        &mut self,
+        board: &Board,
+        ply: usize,
+        depth: Depth,
+        chess_move: Move,
+        previous_move: Option<Move>,
+        tried_moves: &[Move],
+    ) {
+        always!(!is_tactical(chess_move));
+
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(chess_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(chess_move);
+        }
+
+        if let Some(previous_move) = previous_move {
+            let (from, to) = (previous_move.from().index(), previous_move.to().index());
+            self.countermoves[from as usize][to as usize] = Some(chess_move);
+        }
+
+        // NOTE: deeper cutoffs are more reliable
+        let bonus = (depth * depth).min(MAX_HISTORY as usize) as MoveScore;
+        let side = board.side_to_move();
+
+        self.update_history(side, chess_move, bonus);
+        for &tried_move in tried_moves {
+            if !is_tactical(tried_move) {
+                self.update_history(side, tried_move, -bonus);
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn countermove(&self, previous_move: Move) -> Option<Move> {
+        let (from, to) = (previous_move.from().index(), previous_move.to().index());
+
+        self.countermoves[from as usize][to as usize]
+    }
+
+    #[inline(always)]
+    fn history(&self, side: Color, chess_move: Move) -> MoveScore {
+        let (from, to) = (chess_move.from().index(), chess_move.to().index());
+
+        self.history[side.index() as usize][from as usize][to as usize]
+    }
+
+    #[inline(always)]
+    fn update_history(&mut self, side: Color, chess_move: Move, bonus: MoveScore) {
+        let (from, to) = (chess_move.from().index(), chess_move.to().index());
+        let score = &mut self.history[side.index() as usize][from as usize][to as usize];
+
+        *score += bonus - *score * bonus.abs() / MAX_HISTORY;
+    }
+}
+
+#[inline(always)]
+const fn is_tactical(chess_move: Move) -> bool {
+    chess_move.is_capture() || chess_move.is_promotion()
+}
+
+// Most valuable victim / least valuable attacker,
+// victim is known from move itself
+#[inline(always)]
+fn mvv_lva(board: &Board, chess_move: Move) -> MoveScore {
+    let victim = chess_move.captured().index() + chess_move.promoted().index();
+    let attacker = board.piece(chess_move.from()).dignity().index();
+
+    (victim as MoveScore) * 8 - attacker as MoveScore
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(ordering: &MoveOrdering, board: &Board, chess_move: Move) -> MoveScore {
+        ordering.score(board, chess_move, 3, None, Some(Move::quiet(e7, e5)))
+    }
+
+    #[test]
+    fn mvv_lva_order() {
+        let board = Board::from_fen(b"3qk3/1P6/8/3r1p2/2P1P3/8/8/3QK3 w - - 0 1");
+        let ordering = MoveOrdering::new();
+
+        let pawn_takes_rook = score(&ordering, &board, Move::capture(c4, d5, Rook));
+        let queen_takes_rook = score(&ordering, &board, Move::capture(d1, d5, Rook));
+        let pawn_takes_pawn = score(&ordering, &board, Move::capture(e4, f5, Pawn));
+        let promotion = score(&ordering, &board, Move::promotion(b7, b8, Queen));
+        let quiet = score(&ordering, &board, Move::quiet(d1, d2));
+
+        assert!(promotion > pawn_takes_rook);
+        assert!(pawn_takes_rook > queen_takes_rook);
+        assert!(queen_takes_rook > pawn_takes_pawn);
+        assert!(pawn_takes_pawn > quiet);
+
+        assert_eq!(ordering.score_tactical(&board, Move::capture(c4, d5, Rook)), pawn_takes_rook);
+        assert_eq!(ordering.score_tactical(&board, Move::quiet(d1, d2)), MoveScore::MIN);
+    }
+
+    #[test]
+    fn kinds_order() {
+        let board = Board::from_fen(b"4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1");
+        let mut ordering = MoveOrdering::new();
+
+        let hash_move = Move::quiet(d1, a4);
+        let capture = Move::capture(d1, d5, Pawn);
+        let killer = Move::quiet(d1, d2);
+        let countermove = Move::quiet(d1, d3);
+        let history_move = Move::quiet(d1, h5);
+        let quiet = Move::quiet(e1, f2);
+
+        ordering.update_on_cutoff(&board, 3, 5, countermove, Some(Move::quiet(e7, e5)), &[]);
+        ordering.killers[3] = [Some(killer), None];
+        ordering.update_on_cutoff(&board, 7, 5, history_move, None, &[]);
+
+        let score = |chess_move| ordering.score(&board, chess_move, 3, Some(hash_move), Some(Move::quiet(e7, e5)));
+
+        assert!(score(hash_move) > score(capture));
+        assert!(score(capture) > score(killer));
+        assert!(score(killer) > score(countermove));
+        assert!(score(countermove) > score(history_move));
+        assert!(score(history_move) > score(quiet));
+
+        // NOTE: hash move goes first even if it's a killer
+        assert_eq!(ordering.score(&board, killer, 3, Some(killer), None), HASH_MOVE_SCORE);
+    }
+
+    #[test]
+    fn killers() {
+        let board = Board::from_fen(b"4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let mut ordering = MoveOrdering::new();
+
+        let first = Move::quiet(d1, d2);
+        let second = Move::quiet(d1, d3);
+
+        ordering.update_on_cutoff(&board, 3, 1, first, None, &[]);
+        ordering.update_on_cutoff(&board, 3, 1, first, None, &[]);
+        assert_eq!(ordering.killers[3], [Some(first), None]);
+
+        ordering.update_on_cutoff(&board, 3, 1, second, None, &[]);
+        assert_eq!(ordering.killers[3], [Some(second), Some(first)]);
+        assert!(score(&ordering, &board, second) > score(&ordering, &board, first));
+
+        // NOTE: killers are per ply
+        assert_eq!(ordering.killers[4], [None, None]);
+
+        ordering.new_search();
+        assert_eq!(ordering.killers[3], [None, None]);
+    }
+
+    #[test]
+    fn history_rewards_cutoffs_and_punishes_tried_moves() {
+        let board = Board::from_fen(b"4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let mut ordering = MoveOrdering::new();
+
+        let good = Move::quiet(d1, d4);
+        let bad = Move::quiet(d1, d3);
+        let capture = Move::capture(d1, d8, Queen);
+
+        ordering.update_on_cutoff(&board, 0, 4, good, None, &[bad, capture]);
+        assert_eq!(ordering.history(White, good), 16);
+        assert_eq!(ordering.history(White, bad), -16);
+        assert_eq!(ordering.history(White, capture), 0);
+
+        // NOTE: history is per side
+        assert_eq!(ordering.history(Black, good), 0);
+
+        ordering.new_search();
+        assert_eq!(ordering.history(White, good), 8);
+
+        ordering.clear();
+        assert_eq!(ordering.history(White, good), 0);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let board = Board::from_fen(b"4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let mut ordering = MoveOrdering::new();
+
+        let good = Move::quiet(d1, d4);
+        let bad = Move::quiet(d1, d3);
+        for _ in 0..10_000 {
+            ordering.update_on_cutoff(&board, 0, MAX_SEARCH_DEPTH, good, None, &[bad]);
+        }
+
+        assert!(ordering.history(White, good) <= MAX_HISTORY);
+        assert!(ordering.history(White, bad) >= -MAX_HISTORY);
+        assert!(ordering.history(White, good) < COUNTERMOVE_SCORE);
+    }
+
+    #[test]
+    fn countermoves() {
+        let board = Board::from_fen(b"4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let mut ordering = MoveOrdering::new();
+
+        let previous = Move::quiet(e8, f8);
+        let reply = Move::quiet(d1, d8);
+
+        ordering.update_on_cutoff(&board, 1, 1, reply, Some(previous), &[]);
+        assert_eq!(ordering.countermove(previous), Some(reply));
+        assert_eq!(ordering.countermove(Move::quiet(e8, e7)), None);
+
+        assert_eq!(ordering.score(&board, reply, 5, None, Some(previous)), COUNTERMOVE_SCORE);
+    }
+}
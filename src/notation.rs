@@ -0,0 +1,430 @@
+use std::fmt;
+
+use crate::prelude::*;
+
+// Returned by `Move::from_uci` and `Move::from_san`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveParseError {
+    BadSyntax,
+    IllegalMove,
+    AmbiguousMove,
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveParseError::BadSyntax => write!(f, "bad move syntax"),
+            MoveParseError::IllegalMove => write!(f, "move is illegal in this position"),
+            MoveParseError::AmbiguousMove => write!(f, "move is ambiguous in this position"),
+        }
+    }
+}
+
+impl Move {
+    // Parses move in long algebraic notation (like e2e4 or e7e8q),
+    // returns only legal moves
+    // NOTE: castling is king to destination (e1g1) in standard chess
+    //       and king to rook (e1h1) in Chess960
+    pub fn from_uci(board: &Board, text: &str, chess960: bool) -> Result<Self, MoveParseError> {
+        let text = text.as_bytes();
+        if text.len() != 4 && text.len() != 5 {
+            return Err(MoveParseError::BadSyntax);
+        }
+
+        if parse_square(&text[0..2]).is_none() || parse_square(&text[2..4]).is_none() {
+            return Err(MoveParseError::BadSyntax);
+        }
+
+        if text.len() == 5 && !matches!(text[4].to_ascii_lowercase(), b'n' | b'b' | b'r' | b'q') {
+            return Err(MoveParseError::BadSyntax);
+        }
+
+        let text = text.to_ascii_lowercase();
+        legal_moves(board)
+            .as_slice()
+            .iter()
+            .copied()
+            .find(|chess_move| chess_move.to_uci(chess960).as_bytes() == text)
+            .ok_or(MoveParseError::IllegalMove)
+    }
+
+    // NOTE: castling moves are stored as king captures own rook,
+    //       which is the notation of Chess960 mode
+    pub fn to_uci(self, chess960: bool) -> String {
+        let to = if self.is_castling() && !chess960 {
+            self.castling_rights().king_destination()
+        } else {
+            self.to()
+        };
+
+        let (from_file, from_rank) = self.from().fen();
+        let (to_file, to_rank) = to.fen();
+
+        let mut result = String::with_capacity(5);
+        result.push(from_file as char);
+        result.push(from_rank as char);
+        result.push(to_file as char);
+        result.push(to_rank as char);
+
+        let promoted = self.promoted();
+        if promoted != DignityNone {
+            result.push(promoted.as_char().to_ascii_lowercase());
+        }
+
+        result
+    }
+
+    // Parses move in Standard Algebraic Notation (like Nbd7, exd8=Q+ or O-O),
+    // returns only legal moves
+    // NOTE: missing capture sign and check suffix are tolerated,
+    //       but wrong ones are not, pawn captures still need a file
+    pub fn from_san(board: &Board, text: &str) -> Result<Self, MoveParseError> {
+        let text = text.trim_end_matches(['+', '#', '!', '?']).as_bytes();
+
+        let castling = match text {
+            b"O-O" | b"0-0" => Some(true),
+            b"O-O-O" | b"0-0-0" => Some(false),
+            _ => None,
+        };
+
+        if let Some(king_side) = castling {
+            return legal_moves(board)
+                .as_slice()
+                .iter()
+                .copied()
+                .find(|&m| m.is_castling() && m.is_king_side_castling() == king_side)
+                .ok_or(MoveParseError::IllegalMove);
+        }
+
+        let (moved, text) = match text.split_first() {
+            Some((&c, rest)) if c.is_ascii_uppercase() => match dignity_from_san(c) {
+                Some(dignity) => (dignity, rest),
+                None => return Err(MoveParseError::BadSyntax),
+            },
+            _ => (Pawn, text),
+        };
+
+        let (promoted, text) = match text {
+            [rest @ .., b'=', c] | [rest @ .., c] if c.is_ascii_uppercase() => match dignity_from_san(*c) {
+                Some(dignity) if moved == Pawn && dignity != Pawn && dignity != King => (dignity, rest),
+                _ => return Err(MoveParseError::BadSyntax),
+            },
+            _ => (DignityNone, text),
+        };
+
+        let (text, to) = match text.len().checked_sub(2).and_then(|at| {
+            parse_square(&text[at..]).map(|to| (&text[..at], to))
+        }) {
+            Some(parsed) => parsed,
+            None => return Err(MoveParseError::BadSyntax),
+        };
+
+        let (text, is_capture) = match text {
+            [rest @ .., b'x'] => (rest, true),
+            _ => (text, false),
+        };
+
+        let (from_file, from_rank) = match *text {
+            [] => (None, None),
+            [file @ b'a'..=b'h'] => (Some(file), None),
+            [rank @ b'1'..=b'8'] => (None, Some(rank)),
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => (Some(file), Some(rank)),
+            _ => return Err(MoveParseError::BadSyntax),
+        };
+
+        let buffer = legal_moves(board);
+        let mut found = buffer.as_slice().iter().copied().filter(|&m| {
+            let (file, rank) = m.from().fen();
+
+            !m.is_castling()
+                && board.piece(m.from()).dignity() == moved
+                && m.to() == to
+                && m.promoted() == promoted
+                && (!is_capture || m.is_capture())
+                && (moved != Pawn || from_file.is_some() || !m.is_capture())
+                && from_file.unwrap_or(file) == file
+                && from_rank.unwrap_or(rank) == rank
+        });
+
+        match (found.next(), found.next()) {
+            (Some(chess_move), None) => Ok(chess_move),
+            (Some(_), Some(_)) => Err(MoveParseError::AmbiguousMove),
+            (None, _) => Err(MoveParseError::IllegalMove),
+        }
+    }
+
+    // Formats legal move in Standard Algebraic Notation
+    // NOTE: it generates all legal moves (twice, for disambiguation
+    //       and for check suffix), so it's too slow for search
+    pub fn to_san(self, board: &Board) -> String {
+        let mut result = String::with_capacity(8);
+
+        if self.is_castling() {
+            result.push_str(if self.is_king_side_castling() { "O-O" } else { "O-O-O" });
+        } else {
+            let moved = board.piece(self.from()).dignity();
+            let (from_file, from_rank) = self.from().fen();
+            let (to_file, to_rank) = self.to().fen();
+
+            if moved == Pawn {
+                if self.is_capture() {
+                    result.push(from_file as char);
+                }
+            } else {
+                result.push(moved.as_char());
+
+                let mut same_file = false;
+                let mut same_rank = false;
+                let mut ambiguous = false;
+                for &other in legal_moves(board).as_slice() {
+                    if other == self
+                        || other.is_castling()
+                        || other.to() != self.to()
+                        || board.piece(other.from()).dignity() != moved
+                    {
+                        continue;
+                    }
+
+                    ambiguous = true;
+                    same_file |= other.from().file() == self.from().file();
+                    same_rank |= other.from().rank() == self.from().rank();
+                }
+
+                if ambiguous && (!same_file || same_rank) {
+                    result.push(from_file as char);
+                }
+
+                if same_file {
+                    result.push(from_rank as char);
+                }
+            }
+
+            if self.is_capture() {
+                result.push('x');
+            }
+
+            result.push(to_file as char);
+            result.push(to_rank as char);
+
+            let promoted = self.promoted();
+            if promoted != DignityNone {
+                result.push('=');
+                result.push(promoted.as_char());
+            }
+        }
+
+        let movegen = MoveGenerator::new();
+        let mut board = board.clone();
+        movegen.make_move_unchecked(&mut board, self);
+
+        if movegen.is_in_check(&board) {
+            match movegen.game_result(&board) {
+                GameResult::Checkmate { .. } => result.push('#'),
+                _ => result.push('+'),
+            }
+        }
+
+        result
+    }
+}
+
+fn legal_moves(board: &Board) -> MoveBuffer {
+    let mut buffer = MoveBuffer::new();
+    MoveGenerator::new().generate_legal(board, &mut buffer);
+
+    buffer
+}
+
+fn parse_square(text: &[u8]) -> Option<Square> {
+    match *text {
+        [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some(Square::from_fen(file, rank)),
+        _ => None,
+    }
+}
+
+fn dignity_from_san(c: u8) -> Option<Dignity> {
+    match c {
+        b'P' => Some(Pawn),
+        b'N' => Some(Knight),
+        b'B' => Some(Bishop),
+        b'R' => Some(Rook),
+        b'Q' => Some(Queen),
+        b'K' => Some(King),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uci(board: &Board, text: &str) -> Result<Move, MoveParseError> {
+        Move::from_uci(board, text, false)
+    }
+
+    fn san(fen: &str, text: &str) -> Result<String, MoveParseError> {
+        let board = Board::from_fen(fen.as_bytes());
+
+        Move::from_san(&board, text).map(|chess_move| chess_move.to_san(&board))
+    }
+
+    #[test]
+    fn uci_roundtrip() {
+        let board = Board::from_fen(b"r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        for text in ["e1g1", "e1c1", "a1a8", "b7b8q", "b7b8n", "e1d1"] {
+            assert_eq!(uci(&board, text).unwrap().to_uci(false), text);
+        }
+
+        assert_eq!(uci(&board, "b7b8Q").unwrap().to_uci(false), "b7b8q");
+        assert_eq!(Move::from_uci(&board, "e1h1", true).unwrap().to_uci(true), "e1h1");
+    }
+
+    #[test]
+    fn uci_errors() {
+        let board = Board::from_fen(b"r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        assert_eq!(uci(&board, ""), Err(MoveParseError::BadSyntax));
+        assert_eq!(uci(&board, "e1"), Err(MoveParseError::BadSyntax));
+        assert_eq!(uci(&board, "e1i1"), Err(MoveParseError::BadSyntax));
+        assert_eq!(uci(&board, "b7b8k"), Err(MoveParseError::BadSyntax));
+        assert_eq!(uci(&board, "e1g1qq"), Err(MoveParseError::BadSyntax));
+
+        assert_eq!(uci(&board, "e1e3"), Err(MoveParseError::IllegalMove));
+        assert_eq!(uci(&board, "b7b8"), Err(MoveParseError::IllegalMove));
+        assert_eq!(uci(&board, "e8e7"), Err(MoveParseError::IllegalMove));
+
+        // NOTE: king to rook is illegal king move in standard mode
+        assert_eq!(uci(&board, "e1h1"), Err(MoveParseError::IllegalMove));
+        assert_eq!(Move::from_uci(&board, "e1g1", true), Err(MoveParseError::IllegalMove));
+    }
+
+    #[test]
+    fn san_pawn_moves() {
+        let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2";
+
+        assert_eq!(san(fen, "e5"), Ok(String::from("e5")));
+        assert_eq!(san(fen, "exd5"), Ok(String::from("exd5")));
+        assert_eq!(san(fen, "ed5"), Ok(String::from("exd5")));
+        assert_eq!(san(fen, "d5"), Err(MoveParseError::IllegalMove));
+        assert_eq!(san(fen, "exe5"), Err(MoveParseError::IllegalMove));
+        assert_eq!(san(fen, "e4e5"), Ok(String::from("e5")));
+        assert_eq!(san(fen, "e"), Err(MoveParseError::BadSyntax));
+        assert_eq!(san(fen, "e9"), Err(MoveParseError::BadSyntax));
+        assert_eq!(san(fen, "ie5"), Err(MoveParseError::BadSyntax));
+
+        let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(san(en_passant, "exd6"), Ok(String::from("exd6")));
+        assert_eq!(san(en_passant, "e6"), Ok(String::from("e6")));
+    }
+
+    #[test]
+    fn san_promotions() {
+        let fen = "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+
+        assert_eq!(san(fen, "a8=Q"), Ok(String::from("a8=Q")));
+        assert_eq!(san(fen, "a8Q"), Ok(String::from("a8=Q")));
+        assert_eq!(san(fen, "axb8=N"), Ok(String::from("axb8=N")));
+        assert_eq!(san(fen, "a8"), Err(MoveParseError::IllegalMove));
+        assert_eq!(san(fen, "a8=K"), Err(MoveParseError::BadSyntax));
+    }
+
+    #[test]
+    fn san_disambiguation() {
+        // NOTE: knights on b1 and f3 both go to d2,
+        //       rooks on a1 and a5 both go to a3
+        let fen = "4k3/8/8/R7/8/5N2/8/RN2K3 w - - 0 1";
+
+        assert_eq!(san(fen, "Nbd2"), Ok(String::from("Nbd2")));
+        assert_eq!(san(fen, "Nfd2"), Ok(String::from("Nfd2")));
+        assert_eq!(san(fen, "Nd2"), Err(MoveParseError::AmbiguousMove));
+        assert_eq!(san(fen, "Ng5"), Ok(String::from("Ng5")));
+
+        assert_eq!(san(fen, "R1a3"), Ok(String::from("R1a3")));
+        assert_eq!(san(fen, "R5a3"), Ok(String::from("R5a3")));
+        assert_eq!(san(fen, "Ra3"), Err(MoveParseError::AmbiguousMove));
+        assert_eq!(san(fen, "Raa3"), Err(MoveParseError::AmbiguousMove));
+        assert_eq!(san(fen, "Ra1a3"), Ok(String::from("R1a3")));
+
+        // NOTE: queens on b2, b6 and f2 all go to d4,
+        //       so queen on b2 needs both file and rank
+        let fen = "8/8/1Q6/7k/8/8/1Q3Q2/4K3 w - - 0 1";
+
+        assert_eq!(san(fen, "Qb2d4"), Ok(String::from("Qb2d4")));
+        assert_eq!(san(fen, "Q6d4"), Ok(String::from("Q6d4")));
+        assert_eq!(san(fen, "Qbd4"), Err(MoveParseError::AmbiguousMove));
+        assert_eq!(san(fen, "Qfd4"), Ok(String::from("Qfd4")));
+        assert_eq!(san(fen, "Q2d4"), Err(MoveParseError::AmbiguousMove));
+        assert_eq!(san(fen, "Qd4"), Err(MoveParseError::AmbiguousMove));
+    }
+
+    #[test]
+    fn san_captures_and_checks() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 2 3";
+
+        assert_eq!(san(fen, "Qxf7#"), Ok(String::from("Qxf7#")));
+        assert_eq!(san(fen, "Qf7"), Ok(String::from("Qxf7#")));
+        assert_eq!(san(fen, "Bxf7+"), Ok(String::from("Bxf7+")));
+        assert_eq!(san(fen, "Qxh7"), Ok(String::from("Qxh7")));
+        assert_eq!(san(fen, "Qxg6"), Err(MoveParseError::IllegalMove));
+        assert_eq!(san(fen, "Kf1"), Ok(String::from("Kf1")));
+        assert_eq!(san(fen, "Xf1"), Err(MoveParseError::BadSyntax));
+    }
+
+    #[test]
+    fn san_castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+        assert_eq!(san(fen, "O-O"), Ok(String::from("O-O")));
+        assert_eq!(san(fen, "0-0-0"), Ok(String::from("O-O-O")));
+        assert_eq!(san(fen, "Kg1"), Err(MoveParseError::IllegalMove));
+
+        // NOTE: Chess960 castling uses the same notation
+        let chess960 = "1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1";
+        assert_eq!(san(chess960, "O-O"), Ok(String::from("O-O")));
+        assert_eq!(san(chess960, "O-O-O"), Ok(String::from("O-O-O")));
+
+        let no_right = "r3k2r/8/8/8/8/8/8/R3K1R1 w Qkq - 0 1";
+        assert_eq!(san(no_right, "O-O"), Err(MoveParseError::IllegalMove));
+    }
+
+    #[test]
+    fn san_mate_suffix() {
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+        assert_eq!(san(fen, "Ra8"), Ok(String::from("Ra8#")));
+        assert_eq!(san(fen, "Ra8+"), Ok(String::from("Ra8#")));
+    }
+}
+
+#[cfg(test)]
+mod fuzz {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = FastRng::from_system_time();
+
+        for i in 0..(1_101 * FUZZ_MULTIPLIER) {
+            let board = Board::rand(&mut rng);
+
+            if !board.has_possible_pawn_structure() ||
+               !board.has_possible_en_passant_square() ||
+               !board.has_possible_kings_setup() ||
+               !board.has_possible_checks()
+            {
+                continue;
+            }
+
+            for &chess_move in legal_moves(&board).as_slice() {
+                for chess960 in [false, true] {
+                    let text = chess_move.to_uci(chess960);
+                    assert_eq!(Move::from_uci(&board, &text, chess960), Ok(chess_move));
+                }
+
+                let text = chess_move.to_san(&board);
+                assert_eq!(Move::from_san(&board, &text), Ok(chess_move), "{} {text}", board.debug_fen());
+            }
+        }
+    }
+}
@@ -1,38 +1,54 @@
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use crate::prelude::*;
 
 // PERF: try other types
 type Depth = usize;
 
-fn perft_recursive<const TT_SIZE: usize>(
+// NOTE: it's shared between threads in `perft_divide`
+pub const PERFT_HASH_SIZE: usize = 64; // MB
+
+fn perft_recursive(
     board: &mut Board,
     movegen: &MoveGenerator,
     move_buffer: &mut MoveBuffer,
-    transposition_table: &mut TranspositionTable<TT_SIZE>,
+    transposition_table: &TranspositionTable<usize>,
     depth: Depth,
 ) -> usize {
     if depth == 0 {
         return 1;
     }
 
+    // TODO: find a better way to track depth
+    let start_move_index = move_buffer.len();
+
+    // NOTE: all generated moves are legal, so there is no need to make them
+    if depth == 1 {
+        movegen.generate_legal(board, move_buffer);
+        let result = move_buffer.len() - start_move_index;
+        move_buffer.restore_cursor(start_move_index);
+
+        return result;
+    }
+
     if let Some(nodes) = transposition_table.get(board, depth) {
         // TODO: hash-match / missmatch statistics
         return nodes;
     };
 
-    // TODO: find a better way to track depth
-    let start_move_index = move_buffer.len();
-    movegen.generate(board, move_buffer);
+    movegen.generate_legal(board, move_buffer);
     let end_move_index = move_buffer.len();
 
     let mut result = 0;
     // TODO: use iterators
     for move_index in start_move_index..end_move_index {
         let chess_move = move_buffer.get(move_index);
-        let legal = movegen.make_move(board, chess_move);
-        if legal {
-            result += perft_recursive(board, movegen, move_buffer, transposition_table, depth - 1);
-        }
 
+        movegen.make_move_unchecked(board, chess_move);
+        result += perft_recursive(board, movegen, move_buffer, transposition_table, depth - 1);
         movegen.unmake_move(board, chess_move);
     }
 
@@ -43,17 +59,27 @@ fn perft_recursive<const TT_SIZE: usize>(
     result
 }
 
-pub fn perft(fen: &[u8], depth: Depth) -> usize {
-    let mut board = Board::from_fen(fen);
+// NOTE: hash table is used only above the last ply,
+//       so it isn't allocated for shallow perft
+pub fn perft_transposition_table(depth: Depth) -> TranspositionTable<usize> {
+    if depth > 1 {
+        TranspositionTable::new(PERFT_HASH_SIZE)
+    } else {
+        TranspositionTable::with_buckets(1)
+    }
+}
+
+pub fn perft(board: &Board, depth: Depth) -> usize {
+    let mut board = board.clone();
     let movegen = MoveGenerator::new();
     let mut move_buffer = MoveBuffer::new();
-    let mut transposition_table = TranspositionTable::<{512 * 1024}>::new_box();
+    let transposition_table = perft_transposition_table(depth);
 
     let result = perft_recursive(
         &mut board,
         &movegen,
         &mut move_buffer,
-        &mut transposition_table,
+        &transposition_table,
         depth,
     );
 
@@ -66,49 +92,281 @@ pub fn perft(fen: &[u8], depth: Depth) -> usize {
     result
 }
 
-pub fn perft_threaded(fen: &[u8], depth: Depth) -> usize {
-    let mut board = Board::from_fen(fen);
-    let movegen = MoveGenerator::new();
-    let mut move_buffer = MoveBuffer::new();
-    let mut threads = Vec::with_capacity(16);
+// Settings of threaded perft
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PerftThreads {
+    // Size of worker pool
+    pub threads: usize,
 
-    movegen.generate(&board, &mut move_buffer);
+    // Depth at which tree is split into tasks,
+    // 1 means root moves and 2 means their replies
+    pub split_depth: Depth,
+}
 
-    for move_index in 0..move_buffer.len() {
-        let chess_move = move_buffer.get(move_index);
-        let legal = movegen.make_move(&mut board, chess_move);
-        if legal {
-            let mut child_board = board.clone();
-            threads.push(std::thread::spawn(move || {
-                let child_movegen = MoveGenerator::new();
-                let mut child_move_buffer = MoveBuffer::new();
-                let mut child_transposition_table = TranspositionTable::<{512 * 1024}>::new_box();
-
-                let result = perft_recursive(
-                    &mut child_board,
-                    &child_movegen,
-                    &mut child_move_buffer,
-                    &mut child_transposition_table,
-                    depth - 1,
-                );
-
-                #[cfg(feature = "transposition_table_stats")]
-                child_transposition_table.print_statistics();
-
-                #[allow(clippy::let_and_return)]
-                result
-            }));
+impl PerftThreads {
+    // NOTE: splitting at root + 1 gives hundreds of tasks,
+    //       so threads stay busy even when root subtrees
+    //       differ in size by orders of magnitude
+    pub const DEFAULT_SPLIT_DEPTH: Depth = 2;
+
+    pub fn new(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+            split_depth: Self::DEFAULT_SPLIT_DEPTH,
         }
+    }
+}
+
+impl Default for PerftThreads {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1);
+
+        Self::new(threads)
+    }
+}
+
+// Tasks are distributed between threads before start,
+// every thread pops tasks from the back of its own deque
+// and steals them from the front of other deques when it's empty
+// NOTE: tasks aren't added after start, so empty queue means work is done
+struct WorkStealingQueue<T> {
+    deques: Vec<Mutex<VecDeque<T>>>,
+}
+
+impl<T> WorkStealingQueue<T> {
+    fn new(threads: usize, tasks: Vec<T>) -> Self {
+        let mut deques: Vec<VecDeque<T>> = (0..threads).map(|_| VecDeque::new()).collect();
+        for (index, task) in tasks.into_iter().enumerate() {
+            deques[index % threads].push_back(task);
+        }
+
+        Self {
+            deques: deques.into_iter().map(Mutex::new).collect(),
+        }
+    }
+
+    fn pop(&self, thread: usize) -> Option<T> {
+        if let Some(task) = self.deques[thread].lock().unwrap().pop_back() {
+            return Some(task);
+        }
+
+        for offset in 1..self.deques.len() {
+            let victim = (thread + offset) % self.deques.len();
+            if let Some(task) = self.deques[victim].lock().unwrap().pop_front() {
+                return Some(task);
+            }
+        }
+
+        None
+    }
+}
+
+// Subtree to count, `root` is index of root move it belongs to
+struct PerftTask {
+    root: usize,
+    board: Board,
+}
 
+// Collects positions which are `depth` plies below `board`
+fn collect_perft_tasks(
+    board: &mut Board,
+    movegen: &MoveGenerator,
+    root: usize,
+    depth: Depth,
+    tasks: &mut Vec<PerftTask>,
+) {
+    if depth == 0 {
+        tasks.push(PerftTask { root, board: board.clone() });
+        return;
+    }
+
+    let mut move_buffer = MoveBuffer::new();
+    movegen.generate_legal(board, &mut move_buffer);
+
+    for &chess_move in move_buffer.as_slice() {
+        movegen.make_move_unchecked(board, chess_move);
+        collect_perft_tasks(board, movegen, root, depth - 1, tasks);
+        movegen.unmake_move(board, chess_move);
+    }
+}
+
+pub fn perft_threaded(
+    board: &Board,
+    depth: Depth,
+    threads: PerftThreads,
+    transposition_table: &TranspositionTable<usize>,
+) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+
+    perft_divide(board, depth, threads, transposition_table)
+        .iter()
+        .map(|&(_, nodes)| nodes)
+        .sum()
+}
+
+// Counts nodes in subtree of every legal root move
+// with fixed pool of threads
+// NOTE: threads share single transposition table, it's passed in,
+//       so it isn't allocated again for every position and depth
+pub fn perft_divide(
+    board: &Board,
+    depth: Depth,
+    threads: PerftThreads,
+    transposition_table: &TranspositionTable<usize>,
+) -> Vec<(Move, usize)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let mut board = board.clone();
+    let movegen = MoveGenerator::new();
+    let mut root_moves = MoveBuffer::new();
+
+    movegen.generate_legal(&board, &mut root_moves);
+
+    let split_depth = threads.split_depth.clamp(1, depth);
+    let mut tasks = Vec::new();
+    for (root, &chess_move) in root_moves.as_slice().iter().enumerate() {
+        movegen.make_move_unchecked(&mut board, chess_move);
+        collect_perft_tasks(&mut board, &movegen, root, split_depth - 1, &mut tasks);
         movegen.unmake_move(&mut board, chess_move);
     }
 
-    let mut result = 0;
-    for thread in threads {
-        result += thread.join().unwrap();
+    let threads = threads.threads.clamp(1, tasks.len().max(1));
+    let queue = WorkStealingQueue::new(threads, tasks);
+    let nodes: Vec<AtomicUsize> = (0..root_moves.len()).map(|_| AtomicUsize::new(0)).collect();
+
+    std::thread::scope(|scope| {
+        for thread in 0..threads {
+            let queue = &queue;
+            let nodes = &nodes;
+
+            scope.spawn(move || {
+                let movegen = MoveGenerator::new();
+                let mut move_buffer = MoveBuffer::new();
+
+                while let Some(mut task) = queue.pop(thread) {
+                    let result = perft_recursive(
+                        &mut task.board,
+                        &movegen,
+                        &mut move_buffer,
+                        transposition_table,
+                        depth - split_depth,
+                    );
+
+                    nodes[task.root].fetch_add(result, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+
+    #[cfg(feature = "transposition_table_stats")]
+    transposition_table.print_statistics();
+
+    root_moves
+        .as_slice()
+        .iter()
+        .zip(nodes)
+        .map(|(&chess_move, nodes)| (chess_move, nodes.into_inner()))
+        .collect()
+}
+
+// Breakdown of moves made at one depth,
+// the same as in https://www.chessprogramming.org/Perft_Results
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PerftStatistics {
+    pub nodes: usize,
+    pub captures: usize,
+    pub en_passants: usize,
+    pub castles: usize,
+    pub promotions: usize,
+    pub checks: usize,
+    pub discovered_checks: usize,
+    pub double_checks: usize,
+    pub checkmates: usize,
+}
+
+// NOTE: it's much slower than `perft`: every move is made
+//       and there are no transposition table and bulk counting,
+//       so use it only to compare with reference tables
+fn perft_statistics_recursive(
+    board: &mut Board,
+    movegen: &MoveGenerator,
+    move_buffer: &mut MoveBuffer,
+    statistics: &mut [PerftStatistics],
+    ply: usize,
+) {
+    if ply == statistics.len() {
+        return;
     }
 
-    result
+    let start_move_index = move_buffer.len();
+    movegen.generate_legal(board, move_buffer);
+    let end_move_index = move_buffer.len();
+
+    for move_index in start_move_index..end_move_index {
+        let chess_move = move_buffer.get(move_index);
+
+        movegen.make_move_unchecked(board, chess_move);
+
+        let stats = &mut statistics[ply];
+        stats.nodes += 1;
+
+        if chess_move.is_capture() { stats.captures += 1 }
+        if chess_move.is_en_passant() { stats.en_passants += 1 }
+        if chess_move.is_promotion() { stats.promotions += 1 }
+
+        if chess_move.is_castling() { stats.castles += 1 }
+
+        let checkers = movegen.checkers(board);
+        if checkers.is_not_empty() {
+            stats.checks += 1;
+
+            // NOTE: check is discovered if it's given by not moved piece,
+            //       double checks aren't counted as discovered ones
+            if checkers.has_many() {
+                stats.double_checks += 1;
+            } else if !checkers.has(chess_move.to()) {
+                stats.discovered_checks += 1;
+            }
+
+            let replies_index = move_buffer.len();
+            movegen.generate_legal(board, move_buffer);
+            if move_buffer.len() == replies_index {
+                stats.checkmates += 1;
+            }
+
+            move_buffer.restore_cursor(replies_index);
+        }
+
+        perft_statistics_recursive(board, movegen, move_buffer, statistics, ply + 1);
+
+        movegen.unmake_move(board, chess_move);
+    }
+
+    move_buffer.restore_cursor(start_move_index);
+}
+
+// Returns statistics for every depth from 1 to `depth`
+pub fn perft_statistics(fen: &[u8], depth: Depth) -> Vec<PerftStatistics> {
+    let mut board = Board::from_fen(fen);
+    let movegen = MoveGenerator::new();
+    let mut move_buffer = MoveBuffer::new();
+    let mut statistics = vec![PerftStatistics::default(); depth];
+
+    perft_statistics_recursive(
+        &mut board,
+        &movegen,
+        &mut move_buffer,
+        &mut statistics,
+        0,
+    );
+
+    statistics
 }
 
 #[cfg(test)]
@@ -122,16 +380,24 @@ mod tests {
     fn startpos() {
         let fen = b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
         for (result, depth, fen) in [
+            (1, 0, fen),
             (20, 1, fen),
             (400, 2, fen),
             (8_902, 3, fen),
             (197_281, 4, fen),
             (4_865_609, 5, fen),
         ] {
-            assert_eq!(perft(fen, depth), result);
+            assert_eq!(perft(&Board::from_fen(fen), depth), result);
         }
     }
 
+    #[test]
+    fn shallow_perft_has_no_hash() {
+        assert_eq!(perft_transposition_table(0).len(), 1);
+        assert_eq!(perft_transposition_table(1).len(), 1);
+        assert!(perft_transposition_table(2).len() > 1);
+    }
+
     #[test]
     fn kiwipete() {
         let fen = b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
@@ -141,7 +407,7 @@ mod tests {
             (97_862, 3, fen),
             (4_085_603, 4, fen),
         ] {
-            assert_eq!(perft(fen, depth), result);
+            assert_eq!(perft(&Board::from_fen(fen), depth), result);
         }
     }
 
@@ -155,7 +421,7 @@ mod tests {
             (43_238, 4, fen),
             (674_624, 5, fen),
         ] {
-            assert_eq!(perft(fen, depth), result);
+            assert_eq!(perft(&Board::from_fen(fen), depth), result);
         }
     }
 
@@ -168,7 +434,29 @@ mod tests {
             (9_467, 3, fen),
             (422_333, 4, fen),
         ] {
-            assert_eq!(perft(fen, depth), result);
+            assert_eq!(perft(&Board::from_fen(fen), depth), result);
+        }
+    }
+
+    #[test]
+    fn chess960() {
+        for (fen, results) in [
+            (
+                &b"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"[..],
+                [21, 528, 12_189, 326_672],
+            ),
+            (
+                b"2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18_002, 667_366],
+            ),
+            (
+                b"b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                [20, 479, 10_471, 273_318],
+            ),
+        ] {
+            for (depth, result) in results.into_iter().enumerate() {
+                assert_eq!(perft(&Board::from_fen(fen), depth + 1), result);
+            }
         }
     }
 
@@ -181,10 +469,150 @@ mod tests {
             (62_379, 3, fen),
             (2_103_487, 4, fen),
         ] {
-            assert_eq!(perft(fen, depth), result);
+            assert_eq!(perft(&Board::from_fen(fen), depth), result);
+        }
+    }
+
+    macro_rules! stats {
+        (
+            $nodes:expr, $captures:expr, $en_passants:expr, $castles:expr,
+            $promotions:expr, $checks:expr, $discovered_checks:expr,
+            $double_checks:expr, $checkmates:expr
+        ) => {
+            PerftStatistics {
+                nodes: $nodes,
+                captures: $captures,
+                en_passants: $en_passants,
+                castles: $castles,
+                promotions: $promotions,
+                checks: $checks,
+                discovered_checks: $discovered_checks,
+                double_checks: $double_checks,
+                checkmates: $checkmates,
+            }
         }
     }
 
+    #[test]
+    fn statistics_startpos() {
+        let fen = b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+        assert_eq!(perft_statistics(fen, 4), vec![
+            stats!(20, 0, 0, 0, 0, 0, 0, 0, 0),
+            stats!(400, 0, 0, 0, 0, 0, 0, 0, 0),
+            stats!(8_902, 34, 0, 0, 0, 12, 0, 0, 0),
+            stats!(197_281, 1_576, 0, 0, 0, 469, 0, 0, 8),
+        ]);
+    }
+
+    #[test]
+    fn statistics_kiwipete() {
+        let fen = b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+        assert_eq!(perft_statistics(fen, 4), vec![
+            stats!(48, 8, 0, 2, 0, 0, 0, 0, 0),
+            stats!(2_039, 351, 1, 91, 0, 3, 0, 0, 0),
+            stats!(97_862, 17_102, 45, 3_162, 0, 993, 0, 0, 1),
+            stats!(4_085_603, 757_163, 1_929, 128_013, 15_172, 25_523, 42, 6, 43),
+        ]);
+    }
+
+    #[test]
+    fn statistics_cpw_position_3() {
+        let fen = b"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+
+        assert_eq!(perft_statistics(fen, 5), vec![
+            stats!(14, 1, 0, 0, 0, 2, 0, 0, 0),
+            stats!(191, 14, 0, 0, 0, 10, 0, 0, 0),
+            stats!(2_812, 209, 2, 0, 0, 267, 3, 0, 0),
+            stats!(43_238, 3_348, 123, 0, 0, 1_680, 106, 0, 17),
+            stats!(674_624, 52_051, 1_165, 0, 0, 52_950, 1_292, 3, 0),
+        ]);
+    }
+
+    #[test]
+    fn statistics_cpw_position_4() {
+        let fen = b"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+
+        assert_eq!(perft_statistics(fen, 3), vec![
+            stats!(6, 0, 0, 0, 0, 0, 0, 0, 0),
+            stats!(264, 87, 0, 6, 48, 10, 0, 0, 0),
+            stats!(9_467, 1_021, 4, 0, 120, 38, 2, 0, 22),
+        ]);
+    }
+
+    #[test]
+    fn divide() {
+        let fen = b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+        let transposition_table = TranspositionTable::new(PERFT_HASH_SIZE);
+
+        for split_depth in [1, 2, 3, 10] {
+            let threads = PerftThreads { threads: 3, split_depth };
+            let divided = perft_divide(&Board::from_fen(fen), 3, threads, &transposition_table);
+
+            assert_eq!(divided.len(), 48);
+            assert_eq!(divided.iter().map(|&(_, nodes)| nodes).sum::<usize>(), 97_862);
+
+            // NOTE: values are from Stockfish `go perft 3`
+            for (chess_move, nodes) in [
+                (Move::castling(e1, h1), 2_059),
+                (Move::capture(d5, e6, Pawn), 2_241),
+                (Move::capture(e5, f7, Pawn), 2_080),
+                (Move::pawn_single(a2, a3), 2_186),
+            ] {
+                assert!(divided.contains(&(chess_move, nodes)));
+            }
+
+            assert!(perft_divide(&Board::from_fen(fen), 0, threads, &transposition_table).is_empty());
+            assert!(perft_divide(&Board::from_fen(fen), 1, threads, &transposition_table).iter().all(|&(_, nodes)| nodes == 1));
+        }
+    }
+
+    #[test]
+    fn threaded() {
+        let fen = b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let transposition_table = TranspositionTable::new(PERFT_HASH_SIZE);
+
+        for threads in [1, 2, 5, 64] {
+            let threads = PerftThreads::new(threads);
+
+            assert_eq!(perft_threaded(&Board::from_fen(fen), 0, threads, &transposition_table), 1);
+            assert_eq!(perft_threaded(&Board::from_fen(fen), 1, threads, &transposition_table), 20);
+            assert_eq!(perft_threaded(&Board::from_fen(fen), 4, threads, &transposition_table), 197_281);
+        }
+
+        assert_eq!(perft_threaded(&Board::from_fen(fen), 4, PerftThreads::default(), &transposition_table), 197_281);
+    }
+
+    #[test]
+    fn threaded_without_moves() {
+        // NOTE: checkmated and stalemated positions have no tasks at all
+        let checkmate = b"rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3";
+        let stalemate = b"7k/5Q2/6K1/8/8/8/8/8 b - - 0 1";
+
+        let transposition_table = TranspositionTable::new(PERFT_HASH_SIZE);
+
+        for fen in [&checkmate[..], &stalemate[..]] {
+            assert_eq!(perft_threaded(&Board::from_fen(fen), 3, PerftThreads::new(4), &transposition_table), 0);
+            assert!(perft_divide(&Board::from_fen(fen), 3, PerftThreads::new(4), &transposition_table).is_empty());
+        }
+    }
+
+    #[test]
+    fn work_stealing_queue() {
+        let queue = WorkStealingQueue::new(2, (0..5).collect());
+
+        // NOTE: thread 0 owns 0, 2, 4 and thread 1 owns 1, 3
+        assert_eq!(queue.pop(1), Some(3));
+        assert_eq!(queue.pop(1), Some(1));
+        assert_eq!(queue.pop(1), Some(0));
+        assert_eq!(queue.pop(0), Some(4));
+        assert_eq!(queue.pop(0), Some(2));
+        assert_eq!(queue.pop(0), None);
+        assert_eq!(queue.pop(1), None);
+    }
+
     #[test]
     fn cpw_position_6() {
         let fen = b"r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
@@ -194,7 +622,7 @@ mod tests {
             (89_890, 3, fen),
             (3_894_594, 4, fen),
         ] {
-            assert_eq!(perft(fen, depth), result);
+            assert_eq!(perft(&Board::from_fen(fen), depth), result);
         }
     }
 }
@@ -207,11 +635,11 @@ mod bench {
 
     macro_rules! bench_perft {
         ($b:ident, $fen:literal, $depth:literal) => {
-            let fen = black_box($fen);
+            let board = black_box(Board::from_fen($fen));
             let depth = black_box($depth);
 
             $b.iter(|| {
-                perft($fen, $depth)
+                perft(&board, depth)
             })
         }
     }
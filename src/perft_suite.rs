@@ -0,0 +1,292 @@
+use std::fmt;
+
+use crate::perft;
+use crate::prelude::*;
+
+// NOTE: EPD positions usually omit halfmove clock and fullmove number
+const FEN_DEFAULT_FIELDS: [&str; 6] = ["", "", "", "", "0", "1"];
+
+// Position of perftsuite-style EPD file:
+// `<fen> ;D1 <nodes> ;D2 <nodes> ...`
+#[derive(Debug, PartialEq, Eq)]
+pub struct PerftSuiteEntry {
+    pub fen: String,
+    pub expected: Vec<(Depth, usize)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PerftSuiteError {
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for PerftSuiteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PerftSuiteMismatch {
+    pub fen: String,
+    pub depth: Depth,
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for PerftSuiteMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ;D{}: expected {}, got {}",
+            self.fen, self.depth, self.expected, self.actual,
+        )
+    }
+}
+
+// Skips empty lines and comments starting with `#`
+pub fn parse_perft_suite(text: &str) -> Result<Vec<PerftSuiteEntry>, PerftSuiteError> {
+    let mut result = Vec::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message| PerftSuiteError { line: line_index + 1, message };
+
+        let mut parts = line.split(';');
+        let mut fields: Vec<&str> = parts
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect();
+
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(error("FEN should have from 4 to 6 fields"));
+        }
+
+        fields.extend_from_slice(&FEN_DEFAULT_FIELDS[fields.len()..]);
+
+        let fen = fields.join(" ");
+        if Board::try_from_fen(fen.as_bytes()).is_err() {
+            return Err(error("invalid FEN"));
+        }
+
+        let mut expected = Vec::new();
+        for part in parts {
+            let mut tokens = part.split_whitespace();
+
+            let depth = tokens
+                .next()
+                .and_then(|token| token.strip_prefix('D'))
+                .and_then(|depth| depth.parse().ok())
+                .ok_or_else(|| error("expected depth like `D1`"))?;
+
+            let nodes = tokens
+                .next()
+                .and_then(|nodes| nodes.parse().ok())
+                .ok_or_else(|| error("expected node count after depth"))?;
+
+            if tokens.next().is_some() {
+                return Err(error("unexpected token after node count"));
+            }
+
+            expected.push((depth, nodes));
+        }
+
+        result.push(PerftSuiteEntry { fen, expected });
+    }
+
+    Ok(result)
+}
+
+// Runs every entry up to `max_depth` and calls `report`
+// for every depth which node count doesn't match expected one
+pub fn run_perft_suite(
+    entries: &[PerftSuiteEntry],
+    max_depth: Depth,
+    threads: perft::PerftThreads,
+    mut report: impl FnMut(&PerftSuiteMismatch),
+) -> Vec<PerftSuiteMismatch> {
+    let mut mismatches = Vec::new();
+    let mut transposition_table = TranspositionTable::new(perft::PERFT_HASH_SIZE);
+
+    for entry in entries {
+        // NOTE: depths of one position share subtrees, so table is kept for them
+        transposition_table.clear();
+        let board = Board::from_fen(entry.fen.as_bytes());
+
+        for &(depth, expected) in &entry.expected {
+            if depth > max_depth {
+                continue;
+            }
+
+            let actual = perft::perft_threaded(&board, depth, threads, &transposition_table);
+            if actual == expected {
+                continue;
+            }
+
+            let mismatch = PerftSuiteMismatch {
+                fen: entry.fen.clone(),
+                depth,
+                expected,
+                actual,
+            };
+
+            report(&mismatch);
+            mismatches.push(mismatch);
+        }
+    }
+
+    mismatches
+}
+
+// Entry point of `marcus perftsuite <file> [max depth] [--threads N] [--split-depth N]`
+// subcommand, returns process exit code
+pub fn run_cli(args: &[String]) -> i32 {
+    const USAGE: &str = "usage: marcus perftsuite <file.epd> [max depth] [--threads N] [--split-depth N]";
+
+    let mut threads = perft::PerftThreads::default();
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let option = match arg.as_str() {
+            "--threads" => &mut threads.threads,
+            "--split-depth" => &mut threads.split_depth,
+            _ => {
+                positional.push(arg);
+                continue;
+            },
+        };
+
+        match args.next().map(|value| value.parse()) {
+            Some(Ok(value)) if value > 0 => *option = value,
+            _ => {
+                eprintln!("{arg} expects positive number");
+                return 2;
+            },
+        }
+    }
+
+    let path = match positional.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("{USAGE}");
+            return 2;
+        },
+    };
+
+    let max_depth = match positional.get(1).map(|depth| depth.parse()) {
+        None => Depth::MAX,
+        Some(Ok(depth)) => depth,
+        Some(Err(_)) => {
+            eprintln!("invalid max depth: {}", positional[1]);
+            return 2;
+        },
+    };
+
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("can't read {path}: {error}");
+            return 2;
+        },
+    };
+
+    let entries = match parse_perft_suite(&text) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("{path}: {error}");
+            return 2;
+        },
+    };
+
+    let mismatches = run_perft_suite(&entries, max_depth, threads, |mismatch| {
+        println!("mismatch: {mismatch}");
+    });
+
+    println!("{} positions, {} mismatches", entries.len(), mismatches.len());
+
+    if mismatches.is_empty() { 0 } else { 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text = "\
+            # comment\n\
+            \n\
+            4k3/8/8/8/8/8/8/4K2R w K - ;D1 15 ;D2 66\n\
+            8/8/8/8/8/8/6k1/4K2R b K - 3 40 ;D1 3\n\
+        ";
+
+        assert_eq!(parse_perft_suite(text), Ok(vec![
+            PerftSuiteEntry {
+                fen: "4k3/8/8/8/8/8/8/4K2R w K - 0 1".to_string(),
+                expected: vec![(1, 15), (2, 66)],
+            },
+            PerftSuiteEntry {
+                fen: "8/8/8/8/8/8/6k1/4K2R b K - 3 40".to_string(),
+                expected: vec![(1, 3)],
+            },
+        ]));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse_perft_suite("4k3/8/8/8/8/8/8/4K2R w ;D1 15").unwrap_err().line,
+            1,
+        );
+
+        for text in [
+            "4k3/8/8/8/8/8/8/4K2R w K - ;1 15",
+            "4k3/8/8/8/8/8/8/4K2R w K - ;D1",
+            "4k3/8/8/8/8/8/8/4K2R w K - ;D1 x",
+            "4k3/8/8/8/8/8/8/4K2R w K - ;D1 15 16",
+            "4k3/8/8/8/8/8/8/4K2X w K - ;D1 15",
+        ] {
+            assert_eq!(parse_perft_suite(&format!("\n{text}")).unwrap_err().line, 2);
+        }
+    }
+
+    #[test]
+    fn reports_mismatches() {
+        let entries = parse_perft_suite("\
+            4k3/8/8/8/8/8/8/4K2R w K - ;D1 15 ;D2 67 ;D3 1\n\
+            rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - ;D1 20 ;D2 400\n\
+        ").unwrap();
+
+        let mut reported = 0;
+        let mismatches = run_perft_suite(&entries, 2, perft::PerftThreads::new(2), |_| reported += 1);
+
+        assert_eq!(reported, 1);
+        assert_eq!(mismatches, vec![
+            PerftSuiteMismatch {
+                fen: "4k3/8/8/8/8/8/8/4K2R w K - 0 1".to_string(),
+                depth: 2,
+                expected: 67,
+                actual: 66,
+            },
+        ]);
+    }
+
+    // NOTE: takes a while, run it with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn suite() {
+        let text = include_str!("../data/perftsuite.epd");
+        let entries = parse_perft_suite(text).unwrap();
+        let mismatches = run_perft_suite(&entries, 6, perft::PerftThreads::default(), |mismatch| {
+            eprintln!("mismatch: {mismatch}");
+        });
+
+        assert!(mismatches.is_empty());
+    }
+}
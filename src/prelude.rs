@@ -1,13 +1,21 @@
+pub use crate::attacks::*;
+pub use crate::bitboard::*;
 pub use crate::board::*;
 pub use crate::buffer::*;
 pub use crate::castling::*;
 pub use crate::chess_move::*;
 pub use crate::color::*;
+pub use crate::eval::*;
+pub use crate::game_result::*;
 pub use crate::hint::*;
 pub use crate::move_generator::*;
+pub use crate::move_ordering::*;
+pub use crate::notation::*;
 pub use crate::piece::*;
 pub use crate::rand::*;
+pub use crate::search::*;
 pub use crate::settings::*;
 pub use crate::square::*;
+pub use crate::time_manager::*;
 pub use crate::transposition_table::*;
 pub use crate::util::*;
@@ -0,0 +1,1099 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::prelude::*;
+
+// PERF: try other types
+pub type Depth = usize;
+pub type Score = i32;
+
+pub type PrincipalVariation = StaticBuffer<Move, MAX_SEARCH_DEPTH>;
+
+pub const SCORE_INFINITY: Score = 32_000;
+pub const SCORE_MATE: Score = 31_000;
+pub const SCORE_DRAW: Score = 0;
+
+// NOTE: scores above this one are mates in less than MAX_SEARCH_DEPTH plies
+pub const SCORE_MATE_BOUND: Score = SCORE_MATE - MAX_SEARCH_DEPTH as Score;
+
+// NOTE: last ply is reserved for leaf evaluation
+const MAX_SEARCH_PLY: usize = MAX_SEARCH_DEPTH - 1;
+
+pub const DEFAULT_HASH_SIZE: usize = 16; // MB
+
+// Depth diversification of Lazy SMP helpers (same as in Stockfish),
+// helper `i` skips iterations where `(depth + SKIP_PHASE[i]) / SKIP_SIZE[i]` is odd
+const SKIP_SIZE: [Depth; 20] = [1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4];
+const SKIP_PHASE: [Depth; 20] = [0, 1, 0, 1, 2, 3, 0, 1, 2, 3, 4, 5, 0, 1, 2, 3, 4, 5, 6, 7];
+
+// NOTE: helpers publish node counts once per this many nodes,
+//       shared counter in every node is too slow
+const NODES_PUBLISH_INTERVAL: usize = 1024;
+
+// Kind of score stored in transposition table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    // Score is exact, it was inside of alpha-beta window
+    Exact,
+
+    // Search failed high, real score is at least this one
+    Lower,
+
+    // Search failed low, real score is at most this one
+    Upper,
+}
+
+// NOTE: scores are stored as i16 to keep slots small
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchEntry {
+    pub score: i16,
+    pub eval: i16,
+    pub best_move: Option<Move>,
+    pub bound: Bound,
+}
+
+// Bit structure (from low bits to high ones):
+// - score (16 bits)
+// - eval (16 bits)
+// - best move (19 bits), zero if there is no move
+// - bound (2 bits)
+impl TranspositionPayload for SearchEntry {
+    #[inline(always)]
+    fn pack(self) -> u64 {
+        // NOTE: zero move is a1a1, so it can't be a real one
+        let best_move = match self.best_move {
+            Some(chess_move) => chess_move.index() as u64,
+            None => 0,
+        };
+
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+
+        (self.score as u16 as u64) |
+        (self.eval as u16 as u64) << 16 |
+        best_move << 32 |
+        bound << 51
+    }
+
+    #[inline(always)]
+    fn unpack(data: u64) -> Self {
+        let best_move = ((data >> 32) as MoveInner) & Move::Mask;
+
+        Self {
+            score: data as u16 as i16,
+            eval: (data >> 16) as u16 as i16,
+            best_move: if best_move == 0 { None } else { Some(Move::from_index(best_move)) },
+            bound: match (data >> 51) & 0b11 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+        }
+    }
+}
+
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: Score,
+    pub depth: Depth,
+    pub nodes: usize,
+    pub pv: Vec<Move>,
+}
+
+pub struct Searcher {
+    // NOTE: first worker runs in the calling thread,
+    //       others are helper threads of Lazy SMP
+    workers: Vec<SearchWorker>,
+
+    transposition_table: TranspositionTable<SearchEntry>,
+
+    // NOTE: set from other thread to abort search (like on UCI `stop`),
+    //       it isn't reset by search, so it's on caller
+    abort: Arc<AtomicBool>,
+}
+
+impl Searcher {
+    pub fn new() -> Self {
+        Self {
+            workers: vec![SearchWorker::new(0)],
+            transposition_table: TranspositionTable::new(DEFAULT_HASH_SIZE),
+            abort: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn abort_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.abort)
+    }
+
+    // NOTE: it drops all transposition table entries
+    pub fn resize_hash(&mut self, megabytes: usize) {
+        self.transposition_table.resize(megabytes);
+    }
+
+    // Forgets everything learned in previous searches,
+    // should be called on new game
+    pub fn clear_hash(&mut self) {
+        self.transposition_table.clear();
+
+        for worker in &mut self.workers {
+            worker.move_ordering.clear();
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        let threads = threads.max(1);
+
+        self.workers.truncate(threads);
+        while self.workers.len() < threads {
+            self.workers.push(SearchWorker::new(self.workers.len()));
+        }
+    }
+
+    // Iterative deepening, calls `report` after every completed iteration
+    // NOTE: first iteration is never aborted, so there is always a move
+    // NOTE: helper threads are Lazy SMP ones, they search the same
+    //       position and share results only via transposition table
+    pub fn search(
+        &mut self,
+        board: &mut Board,
+        limits: &SearchLimits,
+        report: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        self.transposition_table.new_generation();
+
+        let shared = SharedSearch::new(&self.transposition_table, &self.abort, limits, board.side_to_move());
+        let (main, helpers) = unwrap_unchecked!(self.workers.split_first_mut());
+
+        let mut result = std::thread::scope(|scope| {
+            for helper in helpers.iter_mut() {
+                let mut board = board.clone();
+                let shared = &shared;
+
+                scope.spawn(move || helper.search_helper(&mut board, shared));
+            }
+
+            let result = main.search_main(board, &shared, report);
+            shared.stop.store(true, Ordering::Relaxed);
+
+            result
+        });
+
+        // NOTE: all helpers are joined here, so their counts are complete
+        result.nodes = main.total_nodes(&shared);
+
+        result
+    }
+}
+
+// State shared by all threads of single search
+struct SharedSearch<'a> {
+    transposition_table: &'a TranspositionTable<SearchEntry>,
+    time_manager: TimeManager,
+
+    // NOTE: set by main thread when search is over,
+    //       helpers poll it in every node
+    stop: AtomicBool,
+
+    // See `Searcher::abort`
+    abort: &'a AtomicBool,
+
+    // Nodes of all threads, it lags behind real count,
+    // see `NODES_PUBLISH_INTERVAL`
+    nodes: AtomicUsize,
+}
+
+impl<'a> SharedSearch<'a> {
+    fn new(
+        transposition_table: &'a TranspositionTable<SearchEntry>,
+        abort: &'a AtomicBool,
+        limits: &SearchLimits,
+        side: Color,
+    ) -> Self {
+        Self {
+            transposition_table,
+            time_manager: TimeManager::new(limits, side),
+            stop: AtomicBool::new(false),
+            abort,
+            nodes: AtomicUsize::new(0),
+        }
+    }
+}
+
+// State of single search thread
+struct SearchWorker {
+    id: usize,
+
+    movegen: MoveGenerator,
+
+    // NOTE: every ply has its own buffer, cause single
+    //       `MoveBuffer` can't hold moves of a deep search
+    move_buffers: Vec<MoveBuffer>,
+    move_scores: Vec<MoveScores>,
+    move_ordering: MoveOrdering,
+
+    // Move made at every ply of current line,
+    // it's needed to find countermoves
+    move_stack: Vec<Option<Move>>,
+
+    // Triangular PV table, line at `ply` holds
+    // best continuation found from that ply
+    pv_table: Vec<PrincipalVariation>,
+
+    nodes: usize,
+
+    // Part of `nodes` already added to `SharedSearch::nodes`
+    published_nodes: usize,
+
+    // NOTE: set when limits are exceeded in the middle of iteration,
+    //       scores of aborted iteration are garbage then
+    aborted: bool,
+    abortable: bool,
+}
+
+impl SearchWorker {
+    fn new(id: usize) -> Self {
+        Self {
+            id,
+            movegen: MoveGenerator::new(),
+            move_buffers: (0..MAX_SEARCH_DEPTH).map(|_| MoveBuffer::new()).collect(),
+            move_scores: (0..MAX_SEARCH_DEPTH).map(|_| MoveScores::new()).collect(),
+            move_ordering: MoveOrdering::new(),
+            move_stack: vec![None; MAX_SEARCH_DEPTH],
+            pv_table: (0..MAX_SEARCH_DEPTH).map(|_| PrincipalVariation::new()).collect(),
+            nodes: 0,
+            published_nodes: 0,
+            aborted: false,
+            abortable: false,
+        }
+    }
+
+    fn search_main(
+        &mut self,
+        board: &mut Board,
+        shared: &SharedSearch,
+        mut report: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let max_depth = shared.time_manager.max_depth().clamp(1, MAX_SEARCH_PLY);
+
+        self.nodes = 0;
+        self.published_nodes = 0;
+        self.move_ordering.new_search();
+        self.aborted = false;
+        self.abortable = false;
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: -SCORE_INFINITY,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+
+        for depth in 1..=max_depth {
+            let score = self.negamax(board, shared, depth, 0, -SCORE_INFINITY, SCORE_INFINITY);
+            if self.aborted {
+                break;
+            }
+
+            let pv = self.pv_table[0].as_slice();
+
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score,
+                depth,
+                nodes: self.total_nodes(shared),
+                pv: pv.to_vec(),
+            };
+
+            report(&result);
+            self.abortable = true;
+
+            // NOTE: there is no sense to search deeper if position is over
+            //       or if we've found shortest mate possible
+            if result.best_move.is_none() || score.abs() >= SCORE_MATE - depth as Score {
+                break;
+            }
+
+            if shared.time_manager.should_stop(&result) {
+                break;
+            }
+        }
+
+        result
+    }
+
+    // NOTE: helpers skip some depths, so different threads
+    //       search different depths at the same time
+    fn search_helper(&mut self, board: &mut Board, shared: &SharedSearch) {
+        always!(self.id > 0);
+
+        let max_depth = shared.time_manager.max_depth().clamp(1, MAX_SEARCH_PLY);
+        let skip = (self.id - 1) % SKIP_SIZE.len();
+
+        self.nodes = 0;
+        self.published_nodes = 0;
+        self.move_ordering.new_search();
+        self.aborted = false;
+        self.abortable = true;
+
+        for depth in 1..=max_depth {
+            if (depth + SKIP_PHASE[skip]) / SKIP_SIZE[skip] % 2 == 1 {
+                continue;
+            }
+
+            self.negamax(board, shared, depth, 0, -SCORE_INFINITY, SCORE_INFINITY);
+            if self.aborted {
+                break;
+            }
+        }
+
+        self.publish_nodes(shared);
+    }
+
+    #[inline(always)]
+    fn publish_nodes(&mut self, shared: &SharedSearch) {
+        shared.nodes.fetch_add(self.nodes - self.published_nodes, Ordering::Relaxed);
+        self.published_nodes = self.nodes;
+    }
+
+    // Nodes of all threads, including not yet published ones of this thread
+    #[inline(always)]
+    fn total_nodes(&self, shared: &SharedSearch) -> usize {
+        shared.nodes.load(Ordering::Relaxed) + self.nodes - self.published_nodes
+    }
+
+    // NOTE: called after every node count increment,
+    //       every thread watches limits and abort flag,
+    //       helpers also watch main thread
+    // NOTE: node limit is for all threads together, so helpers
+    //       watch limits too, main thread can be descheduled
+    #[inline(always)]
+    fn should_abort(&mut self, shared: &SharedSearch) -> bool {
+        if self.nodes - self.published_nodes >= NODES_PUBLISH_INTERVAL {
+            self.publish_nodes(shared);
+        }
+
+        if !self.aborted && self.abortable {
+            self.aborted = shared.abort.load(Ordering::Relaxed) ||
+                shared.time_manager.should_abort(self.total_nodes(shared)) ||
+                (self.id != 0 && shared.stop.load(Ordering::Relaxed));
+        }
+
+        self.aborted
+    }
+
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        shared: &SharedSearch,
+        depth: Depth,
+        ply: usize,
+        mut alpha: Score,
+        beta: Score,
+    ) -> Score {
+        always!(ply < MAX_SEARCH_DEPTH);
+
+        // NOTE: if position is repeated once, it can be repeated again,
+        //       so there is no need to wait for threefold repetition
+        if ply > 0 && (
+            board.is_repetition() ||
+            board.has_insufficient_material()
+        ) {
+            self.nodes += 1;
+            self.pv_table[ply].reset();
+
+            return SCORE_DRAW;
+        }
+
+        // NOTE: checkmate has priority over fifty-move rule,
+        //       but legal moves are generated only when in check
+        if ply > 0 && board.halfmove_clock() as usize >= FIFTY_MOVE_RULE_PLIES {
+            self.nodes += 1;
+            self.pv_table[ply].reset();
+
+            if self.movegen.is_in_check(board) {
+                self.move_buffers[ply].reset();
+                self.movegen.generate_legal(board, &mut self.move_buffers[ply]);
+
+                if self.move_buffers[ply].len() == 0 {
+                    return -SCORE_MATE + ply as Score;
+                }
+            }
+
+            return SCORE_DRAW;
+        }
+
+        if depth == 0 {
+            return self.quiescence(board, shared, ply, alpha, beta);
+        }
+
+        self.nodes += 1;
+        self.pv_table[ply].reset();
+
+        if self.should_abort(shared) {
+            return SCORE_DRAW;
+        }
+
+        let mut hash_move = None;
+        let mut hash_eval = None;
+        if let Some((entry_depth, entry)) = shared.transposition_table.probe(board) {
+            hash_move = entry.best_move;
+            hash_eval = Some(entry.eval as Score);
+
+            // NOTE: exact scores inside of window aren't used
+            //       to keep principal variation complete
+            let score = score_from_hash(entry.score, ply);
+            if ply > 0 && entry_depth >= depth && match entry.bound {
+                Bound::Exact => score <= alpha || score >= beta,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            } {
+                return score;
+            }
+        }
+
+        // NOTE: static evaluation is computed once per node,
+        //       and it's taken from hash if position was seen before
+        let eval = hash_eval.unwrap_or_else(|| evaluate(board));
+        if ply >= MAX_SEARCH_PLY {
+            return eval;
+        }
+
+        self.move_buffers[ply].reset();
+        self.movegen.generate_legal(board, &mut self.move_buffers[ply]);
+
+        // NOTE: hash move is the best one found by previous searches,
+        //       so it's searched first
+        let previous_move = self.previous_move(ply);
+        self.move_scores[ply].reset();
+        for &chess_move in self.move_buffers[ply].as_slice() {
+            let score = self.move_ordering.score(board, chess_move, ply, hash_move, previous_move);
+            self.move_scores[ply].add(score);
+        }
+
+        let original_alpha = alpha;
+        let mut best_move = None;
+
+        let legal_moves = self.move_buffers[ply].len();
+        for move_index in 0..legal_moves {
+            let chess_move = self.pick_move(ply, move_index);
+            self.move_stack[ply] = Some(chess_move);
+
+            self.movegen.make_move_unchecked(board, chess_move);
+            let score = -self.negamax(board, shared, depth - 1, ply + 1, -beta, -alpha);
+            self.movegen.unmake_move(board, chess_move);
+
+            if self.aborted {
+                return SCORE_DRAW;
+            }
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(chess_move);
+                self.update_pv(ply, chess_move);
+
+                if alpha >= beta {
+                    if !chess_move.is_capture() && !chess_move.is_promotion() {
+                        self.move_ordering.update_on_cutoff(
+                            board,
+                            ply,
+                            depth,
+                            chess_move,
+                            previous_move,
+                            &self.move_buffers[ply].as_slice()[..move_index],
+                        );
+                    }
+
+                    break;
+                }
+            }
+        }
+
+        if legal_moves == 0 {
+            if self.movegen.is_in_check(board) {
+                return -SCORE_MATE + ply as Score;
+            }
+
+            return SCORE_DRAW;
+        }
+
+        let bound = if alpha >= beta {
+            Bound::Lower
+        } else if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        shared.transposition_table.add(board, depth, SearchEntry {
+            score: score_to_hash(alpha, ply),
+            eval: eval as i16,
+            best_move: best_move.or(hash_move),
+            bound,
+        });
+
+        alpha
+    }
+
+    // Searches only captures and promotions to reach quiet position,
+    // so horizon effect doesn't affect leaf evaluations
+    fn quiescence(
+        &mut self,
+        board: &mut Board,
+        shared: &SharedSearch,
+        ply: usize,
+        mut alpha: Score,
+        beta: Score,
+    ) -> Score {
+        always!(ply < MAX_SEARCH_DEPTH);
+
+        self.nodes += 1;
+        self.pv_table[ply].reset();
+
+        if self.should_abort(shared) {
+            return SCORE_DRAW;
+        }
+
+        // NOTE: side to move isn't forced to capture,
+        //       so static evaluation is a lower bound here
+        let stand_pat = evaluate(board);
+        if ply >= MAX_SEARCH_PLY {
+            return stand_pat;
+        }
+
+        if stand_pat >= beta {
+            return beta;
+        }
+
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+
+        self.move_buffers[ply].reset();
+        // PERF: try to generate only tactical moves
+        self.movegen.generate_legal(board, &mut self.move_buffers[ply]);
+
+        self.move_scores[ply].reset();
+        for &chess_move in self.move_buffers[ply].as_slice() {
+            let score = self.move_ordering.score_tactical(board, chess_move);
+            self.move_scores[ply].add(score);
+        }
+
+        // NOTE: tactical moves are picked first, so the rest is quiet
+        for move_index in 0..self.move_buffers[ply].len() {
+            let chess_move = self.pick_move(ply, move_index);
+            if !chess_move.is_capture() && !chess_move.is_promotion() {
+                break;
+            }
+
+            self.move_stack[ply] = Some(chess_move);
+
+            self.movegen.make_move_unchecked(board, chess_move);
+            let score = -self.quiescence(board, shared, ply + 1, -beta, -alpha);
+            self.movegen.unmake_move(board, chess_move);
+
+            if self.aborted {
+                return SCORE_DRAW;
+            }
+
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, chess_move);
+
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        alpha
+    }
+
+    // Moves best scored move to `index` (selection sort step),
+    // sorting all moves is wasted work when cutoff happens early
+    #[inline(always)]
+    fn pick_move(&mut self, ply: usize, index: usize) -> Move {
+        let moves = &mut self.move_buffers[ply];
+        let scores = &mut self.move_scores[ply];
+
+        let mut best_index = index;
+        for move_index in index + 1..moves.len() {
+            if scores.get(move_index) > scores.get(best_index) {
+                best_index = move_index;
+            }
+        }
+
+        moves.swap(index, best_index);
+        scores.swap(index, best_index);
+
+        moves.get(index)
+    }
+
+    #[inline(always)]
+    fn previous_move(&self, ply: usize) -> Option<Move> {
+        if ply == 0 {
+            return None;
+        }
+
+        self.move_stack[ply - 1]
+    }
+
+    #[inline(always)]
+    fn update_pv(&mut self, ply: usize, chess_move: Move) {
+        always!(ply + 1 < MAX_SEARCH_DEPTH);
+
+        let (head, tail) = self.pv_table.split_at_mut(ply + 1);
+        let line = &mut head[ply];
+        let child_line = &tail[0];
+
+        line.reset();
+        line.add(chess_move);
+        for &next_move in child_line.as_slice() {
+            line.add(next_move);
+        }
+    }
+}
+
+// NOTE: mate scores depend on ply, so they're stored
+//       as distance from the node instead of root
+#[inline(always)]
+fn score_to_hash(score: Score, ply: usize) -> i16 {
+    let score = if score >= SCORE_MATE_BOUND {
+        score + ply as Score
+    } else if score <= -SCORE_MATE_BOUND {
+        score - ply as Score
+    } else {
+        score
+    };
+
+    always!(score.abs() <= SCORE_INFINITY);
+
+    score as i16
+}
+
+#[inline(always)]
+fn score_from_hash(score: i16, ply: usize) -> Score {
+    let score = score as Score;
+
+    if score >= SCORE_MATE_BOUND {
+        score - ply as Score
+    } else if score <= -SCORE_MATE_BOUND {
+        score + ply as Score
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{Duration, Instant};
+
+    fn search(fen: &[u8], depth: Depth) -> SearchResult {
+        let mut board = Board::from_fen(fen);
+        let mut searcher = Searcher::new();
+
+        searcher.search(&mut board, &SearchLimits::depth(depth), |_| {})
+    }
+
+    #[test]
+    fn captures_hanging_queen() {
+        let result = search(b"4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 2);
+
+        assert_eq!(result.best_move, Some(Move::capture(d1, d5, Queen)));
+        assert!(result.score > 400);
+    }
+
+    #[test]
+    fn avoids_defended_pawn() {
+        let result = search(b"4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", 1);
+
+        assert_ne!(result.best_move, Some(Move::capture(d1, d5, Pawn)));
+        assert!(result.score > 600);
+    }
+
+    #[test]
+    fn quiescence_resolves_exchanges() {
+        // NOTE: Nxd5 Nxd5 Rxd5 trades knights and wins the pawn, so quiescence
+        //       scores it above the starting rook-for-pawn balance
+        let result = search(b"4k3/8/8/3p4/1n6/2N5/8/3RK3 w - - 0 1", 1);
+
+        assert_eq!(result.best_move, Some(Move::capture(c3, d5, Pawn)));
+        assert!(result.score > 400);
+    }
+
+    #[test]
+    fn quiescence_searches_promotions() {
+        let mut board = Board::from_fen(b"8/3P4/8/8/8/8/k7/4K3 b - - 0 1");
+        let mut searcher = Searcher::new();
+        let shared = SharedSearch::new(
+            &searcher.transposition_table,
+            &searcher.abort,
+            &SearchLimits::default(),
+            White,
+        );
+        let worker = &mut searcher.workers[0];
+        let score = worker.quiescence(&mut board, &shared, 0, -SCORE_INFINITY, SCORE_INFINITY);
+
+        assert_eq!(score, evaluate(&board));
+
+        board.swap_side_to_move();
+        let score = worker.quiescence(&mut board, &shared, 0, -SCORE_INFINITY, SCORE_INFINITY);
+
+        assert!(score > 800);
+        assert_eq!(worker.pv_table[0].as_slice(), &[Move::promotion(d7, d8, Queen)]);
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let result = search(b"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 4);
+
+        assert_eq!(result.best_move, Some(Move::quiet(a1, a8)));
+        assert_eq!(result.score, SCORE_MATE - 1);
+        assert_eq!(result.depth, 2);
+    }
+
+    #[test]
+    fn mate_in_two() {
+        let result = search(b"k7/8/2K5/8/8/8/8/7R w - - 0 1", 4);
+
+        assert_eq!(result.score, SCORE_MATE - 3);
+        assert_eq!(result.pv.len(), 3);
+    }
+
+    #[test]
+    fn checkmated() {
+        let result = search(b"R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", 3);
+
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, -SCORE_MATE);
+    }
+
+    #[test]
+    fn stalemated() {
+        let result = search(b"7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, SCORE_DRAW);
+    }
+
+    #[test]
+    fn repetition_is_draw() {
+        let mut board = Board::from_fen(b"4k3/8/8/8/8/8/q7/4K1N1 w - - 0 1");
+        let movegen = MoveGenerator::new();
+        for chess_move in [
+            Move::quiet(g1, f3),
+            Move::quiet(e8, d8),
+            Move::quiet(f3, g1),
+            Move::quiet(d8, e8),
+        ] {
+            movegen.make_move_unchecked(&mut board, chess_move);
+        }
+
+        // NOTE: white is lost, so it repeats position
+        let mut searcher = Searcher::new();
+        let result = searcher.search(&mut board, &SearchLimits::depth(1), |_| {});
+
+        assert_eq!(result.best_move, Some(Move::quiet(g1, f3)));
+        assert_eq!(result.score, SCORE_DRAW);
+    }
+
+    #[test]
+    fn insufficient_material_is_draw() {
+        let result = search(b"4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", 2);
+
+        assert_eq!(result.score, SCORE_DRAW);
+    }
+
+    #[test]
+    fn fifty_move_rule_is_draw() {
+        // NOTE: every move except capture of rook is the 100th ply
+        let result = search(b"4k3/8/8/8/8/8/4r3/R3K3 w - - 99 80", 2);
+
+        assert_eq!(result.best_move, Some(Move::capture(e1, e2, Rook)));
+        assert!(result.score > 0);
+
+        let result = search(b"4k3/8/8/8/8/8/8/R3K3 w - - 99 80", 2);
+        assert_eq!(result.score, SCORE_DRAW);
+
+        // NOTE: mate on the 100th ply isn't a draw
+        let result = search(b"6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80", 2);
+        assert_eq!(result.best_move, Some(Move::quiet(a1, a8)));
+        assert_eq!(result.score, SCORE_MATE - 1);
+    }
+
+    #[test]
+    fn pv_starts_with_best_move() {
+        let result = search(b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3);
+
+        assert_eq!(result.pv.first().copied(), result.best_move);
+        assert!(result.pv.len() >= 3);
+    }
+
+    #[test]
+    fn search_entry_packing() {
+        for entry in [
+            SearchEntry { score: -SCORE_MATE as i16, eval: 35, best_move: None, bound: Bound::Upper },
+            SearchEntry { score: 120, eval: -4_000, best_move: Some(Move::castling(e8, a8)), bound: Bound::Exact },
+            SearchEntry {
+                score: SCORE_INFINITY as i16,
+                eval: 0,
+                best_move: Some(Move::promotion_capture(b7, a8, Rook, Queen)),
+                bound: Bound::Lower,
+            },
+        ] {
+            assert_eq!(SearchEntry::unpack(entry.pack()), entry);
+        }
+    }
+
+    #[test]
+    fn hash_stores_static_eval() {
+        let mut board = Board::from_fen(b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+        searcher.search(&mut board, &SearchLimits::depth(3), |_| {});
+
+        let (_, entry) = searcher.transposition_table.probe(&board).unwrap();
+        assert_eq!(entry.eval as Score, evaluate(&board));
+    }
+
+    #[test]
+    fn mate_scores_in_hash_are_relative_to_node() {
+        let score = SCORE_MATE - 7;
+        let stored = score_to_hash(score, 4);
+
+        assert_eq!(stored as Score, SCORE_MATE - 3);
+        assert_eq!(score_from_hash(stored, 2), SCORE_MATE - 5);
+        assert_eq!(score_from_hash(score_to_hash(-score, 4), 4), -score);
+        assert_eq!(score_from_hash(score_to_hash(150, 4), 10), 150);
+    }
+
+    #[test]
+    fn hash_reduces_nodes() {
+        let fen = b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut board = Board::from_fen(fen);
+        let mut searcher = Searcher::new();
+
+        let first = searcher.search(&mut board, &SearchLimits::depth(3), |_| {});
+        let second = searcher.search(&mut board, &SearchLimits::depth(3), |_| {});
+        assert_eq!(first.score, second.score);
+        assert!(second.nodes < first.nodes);
+
+        searcher.clear_hash();
+        let cleared = searcher.search(&mut board, &SearchLimits::depth(3), |_| {});
+        assert_eq!(cleared.nodes, first.nodes);
+    }
+
+    #[test]
+    fn search_restores_board() {
+        let fen = b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut board = Board::from_fen(fen);
+        let hash = board.hash();
+        let mut searcher = Searcher::new();
+        searcher.search(&mut board, &SearchLimits::depth(3), |_| {});
+
+        let mut buffer = FenBuffer::new();
+        board.fen(&mut buffer);
+
+        assert_eq!(buffer.as_slice(), fen);
+        assert_eq!(board.hash(), hash);
+    }
+
+    #[test]
+    fn reports_every_iteration() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+
+        let mut depths = Vec::new();
+        searcher.search(&mut board, &SearchLimits::depth(3), |result| depths.push(result.depth));
+
+        assert_eq!(depths, [1, 2, 3]);
+    }
+
+    #[test]
+    fn set_threads() {
+        let mut searcher = Searcher::new();
+        assert_eq!(searcher.threads(), 1);
+
+        searcher.set_threads(4);
+        assert_eq!(searcher.threads(), 4);
+        assert!(searcher.workers.iter().enumerate().all(|(id, worker)| worker.id == id));
+
+        searcher.set_threads(0);
+        assert_eq!(searcher.threads(), 1);
+    }
+
+    #[test]
+    fn threaded_search() {
+        let fen = b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut board = Board::from_fen(fen);
+        let mut searcher = Searcher::new();
+        let single = searcher.search(&mut board, &SearchLimits::depth(4), |_| {});
+
+        searcher.clear_hash();
+        searcher.set_threads(4);
+        let result = searcher.search(&mut board, &SearchLimits::depth(4), |_| {});
+
+        assert_eq!(result.depth, 4);
+        assert!(result.nodes > single.nodes);
+        assert!(result.best_move.is_some());
+
+        let mut buffer = FenBuffer::new();
+        board.fen(&mut buffer);
+        assert_eq!(buffer.as_slice(), fen);
+    }
+
+    #[test]
+    fn threaded_search_finds_mate() {
+        let mut board = Board::from_fen(b"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let mut searcher = Searcher::new();
+        searcher.set_threads(3);
+
+        let result = searcher.search(&mut board, &SearchLimits::depth(4), |_| {});
+        assert_eq!(result.best_move, Some(Move::quiet(a1, a8)));
+        assert_eq!(result.score, SCORE_MATE - 1);
+    }
+
+    #[test]
+    fn threaded_search_stops_on_time() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+        searcher.set_threads(4);
+        let limits = SearchLimits { movetime: Some(Duration::from_millis(100)), ..SearchLimits::default() };
+
+        let start = Instant::now();
+        let result = searcher.search(&mut board, &limits, |_| {});
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn node_limit_stops_search() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+        let limits = SearchLimits { nodes: Some(5_000), ..SearchLimits::default() };
+
+        let result = searcher.search(&mut board, &limits, |_| {});
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 5_000);
+        assert!(result.depth < MAX_SEARCH_PLY);
+    }
+
+    #[test]
+    fn node_limit_counts_helper_nodes() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+        searcher.set_threads(4);
+        let limits = SearchLimits { nodes: Some(50_000), ..SearchLimits::default() };
+
+        // NOTE: helpers publish their counts with a lag,
+        //       so limit can be exceeded a bit
+        let result = searcher.search(&mut board, &limits, |_| {});
+        assert!(result.best_move.is_some());
+        assert!(result.nodes < 50_000 + 4 * 2 * NODES_PUBLISH_INTERVAL);
+    }
+
+    #[test]
+    fn abort_flag_stops_search() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+        searcher.set_threads(2);
+
+        // NOTE: first iteration is completed anyway
+        searcher.abort_flag().store(true, Ordering::Relaxed);
+        let result = searcher.search(&mut board, &SearchLimits::default(), |_| {});
+        assert_eq!(result.depth, 1);
+        assert!(result.best_move.is_some());
+
+        let abort = searcher.abort_flag();
+        abort.store(false, Ordering::Relaxed);
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(50));
+                abort.store(true, Ordering::Relaxed);
+            });
+
+            searcher.search(&mut board, &SearchLimits::default(), |_| {})
+        });
+        assert!(result.depth < MAX_SEARCH_PLY);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn first_iteration_is_never_aborted() {
+        let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+        let limits = SearchLimits { movetime: Some(Duration::ZERO), ..SearchLimits::default() };
+
+        let result = searcher.search(&mut board, &limits, |_| {});
+        assert_eq!(result.depth, 1);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn movetime_stops_search() {
+        let mut board = Board::from_fen(b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+        let limits = SearchLimits { movetime: Some(Duration::from_millis(100)), ..SearchLimits::default() };
+
+        let start = Instant::now();
+        let result = searcher.search(&mut board, &limits, |_| {});
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(result.best_move.is_some());
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use super::*;
+
+    use test::{Bencher, black_box};
+
+    #[bench]
+    fn search_startpos(b: &mut Bencher) {
+        let board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+
+        b.iter(|| {
+            let mut board = black_box(board.clone());
+            searcher.search(&mut board, &SearchLimits::depth(3), |_| {})
+        })
+    }
+
+    // NOTE: time to depth from empty hash,
+    //       ratio of these benches is a speedup of Lazy SMP
+    fn time_to_depth(b: &mut Bencher, threads: usize) {
+        let board = Board::from_fen(b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let mut searcher = Searcher::new();
+        searcher.set_threads(threads);
+
+        b.iter(|| {
+            searcher.clear_hash();
+
+            let mut board = black_box(board.clone());
+            searcher.search(&mut board, &SearchLimits::depth(6), |_| {})
+        })
+    }
+
+    #[bench]
+    fn time_to_depth_1_thread(b: &mut Bencher) {
+        time_to_depth(b, 1);
+    }
+
+    #[bench]
+    fn time_to_depth_2_threads(b: &mut Bencher) {
+        time_to_depth(b, 2);
+    }
+
+    #[bench]
+    fn time_to_depth_4_threads(b: &mut Bencher) {
+        time_to_depth(b, 4);
+    }
+}
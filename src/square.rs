@@ -190,15 +190,6 @@ impl Square {
 
         Self::from_file_rank(en_passant_file, en_passant_rank)
     }
-
-    #[inline(always)]
-    pub const fn king_initial(side_to_move: Color) -> Self {
-        match side_to_move {
-            Black => e8,
-            White => e1,
-            _ => never!(),
-        }
-    }
 }
 
 impl fmt::Debug for Square {
@@ -322,6 +313,11 @@ impl File {
         Self::from_index(fen - b'a')
     }
 
+    #[inline(always)]
+    pub const fn index(self) -> SquareInner {
+        self.0
+    }
+
     #[inline(always)]
     pub const fn a_to_h() -> FileIterator {
         FileIterator(FileA)
@@ -0,0 +1,317 @@
+use std::time::{Duration, Instant};
+
+use crate::prelude::*;
+
+// NOTE: used when clock is given without `movestogo`,
+//       also caps `movestogo` so long time controls aren't too slow
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(10);
+
+// NOTE: clock is checked only once per this many nodes,
+//       `Instant::now` is too slow to call in every node
+const TIME_CHECK_INTERVAL: usize = 1024;
+
+// Limits of single search, as given by `go` command
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<Depth>,
+    pub nodes: Option<usize>,
+
+    // NOTE: in moves, not plies
+    pub mate: Option<usize>,
+
+    pub movetime: Option<Duration>,
+
+    // Remaining time and increment of each side, indexed by color
+    pub time: [Option<Duration>; 2],
+    pub increment: [Duration; 2],
+    pub moves_to_go: Option<u32>,
+
+    // Time lost on communication with GUI every move
+    pub move_overhead: Duration,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: Depth) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+}
+
+// Turns search limits into deadlines:
+// - soft one: new iteration isn't started after it
+// - hard one: search is aborted after it
+pub struct TimeManager {
+    start: Instant,
+    soft_deadline: Option<Duration>,
+    hard_deadline: Option<Duration>,
+    max_nodes: Option<usize>,
+    max_depth: Depth,
+    mate: Option<usize>,
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, side: Color) -> Self {
+        let (soft_deadline, hard_deadline) = Self::deadlines(limits, side);
+
+        Self {
+            start: Instant::now(),
+            soft_deadline,
+            hard_deadline,
+            max_nodes: limits.nodes,
+            max_depth: Self::max_depth_of(limits),
+            mate: limits.mate,
+        }
+    }
+
+    // NOTE: mate in N moves is found by full-width search of 2N plies
+    //       (last one is to see there are no replies), so deeper
+    //       search can't find it anyway
+    fn max_depth_of(limits: &SearchLimits) -> Depth {
+        let mate_depth = limits.mate.map_or(MAX_SEARCH_DEPTH, |moves| (2 * moves).max(1));
+
+        limits.depth.unwrap_or(MAX_SEARCH_DEPTH).min(mate_depth)
+    }
+
+    fn deadlines(limits: &SearchLimits, side: Color) -> (Option<Duration>, Option<Duration>) {
+        let overhead = limits.move_overhead;
+
+        if let Some(movetime) = limits.movetime {
+            let deadline = movetime.saturating_sub(overhead);
+
+            return (Some(deadline), Some(deadline));
+        }
+
+        let time = match limits.time[side.index() as usize] {
+            Some(time) => time,
+            None => return (None, None),
+        };
+
+        let increment = limits.increment[side.index() as usize];
+        let moves_to_go = limits.moves_to_go
+            .unwrap_or(DEFAULT_MOVES_TO_GO)
+            .clamp(1, DEFAULT_MOVES_TO_GO);
+
+        // NOTE: increment is added after the move is made,
+        //       so hard deadline never goes beyond remaining time
+        let available = time.saturating_sub(overhead);
+        let optimum = available / moves_to_go + increment * 3 / 4;
+        let hard = (optimum * 3).min(available * 3 / 4);
+        let soft = optimum.min(hard);
+
+        (Some(soft), Some(hard))
+    }
+
+    #[inline(always)]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[inline(always)]
+    pub const fn max_depth(&self) -> Depth {
+        self.max_depth
+    }
+
+    // Checked in every node, so it looks at clock
+    // only once per `TIME_CHECK_INTERVAL` nodes
+    #[inline(always)]
+    pub fn should_abort(&self, nodes: usize) -> bool {
+        if let Some(max_nodes) = self.max_nodes {
+            if nodes >= max_nodes {
+                return true;
+            }
+        }
+
+        match self.hard_deadline {
+            Some(deadline) => nodes % TIME_CHECK_INTERVAL == 0 && self.elapsed() >= deadline,
+            None => false,
+        }
+    }
+
+    // Checked after every completed iteration
+    pub fn should_stop(&self, result: &SearchResult) -> bool {
+        if result.depth >= self.max_depth {
+            return true;
+        }
+
+        if let Some(moves) = self.mate {
+            if result.score >= SCORE_MATE - (2 * moves as Score - 1) {
+                return true;
+            }
+        }
+
+        match self.soft_deadline {
+            Some(deadline) => self.elapsed() >= deadline,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NOTE: black has plenty of time, so it's visible when wrong clock is used
+    fn clock(time: u64, increment: u64, moves_to_go: Option<u32>) -> SearchLimits {
+        let mut limits = SearchLimits {
+            moves_to_go,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            ..SearchLimits::default()
+        };
+
+        limits.time[White.index() as usize] = Some(Duration::from_millis(time));
+        limits.time[Black.index() as usize] = Some(Duration::from_secs(3600));
+        limits.increment[White.index() as usize] = Duration::from_millis(increment);
+
+        limits
+    }
+
+    fn deadlines(limits: &SearchLimits) -> (Duration, Duration) {
+        let (soft, hard) = TimeManager::deadlines(limits, White);
+
+        (soft.unwrap(), hard.unwrap())
+    }
+
+    #[test]
+    fn no_clock_has_no_deadlines() {
+        assert_eq!(TimeManager::deadlines(&SearchLimits::default(), White), (None, None));
+        assert_eq!(TimeManager::deadlines(&SearchLimits::depth(5), White), (None, None));
+
+        // NOTE: only clock of side to move matters
+        let mut limits = SearchLimits::default();
+        limits.time[White.index() as usize] = Some(Duration::from_secs(1));
+        assert_eq!(TimeManager::deadlines(&limits, Black), (None, None));
+    }
+
+    #[test]
+    fn movetime() {
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(500)),
+            move_overhead: Duration::from_millis(20),
+            ..clock(1000, 0, None)
+        };
+
+        let deadline = Duration::from_millis(480);
+        assert_eq!(deadlines(&limits), (deadline, deadline));
+    }
+
+    #[test]
+    fn sudden_death() {
+        let (soft, hard) = deadlines(&clock(60_010, 0, None));
+
+        assert_eq!(soft, Duration::from_secs(2));
+        assert_eq!(hard, Duration::from_secs(6));
+    }
+
+    #[test]
+    fn increment() {
+        let (soft, hard) = deadlines(&clock(30_010, 1000, None));
+
+        assert_eq!(soft, Duration::from_millis(1750));
+        assert_eq!(hard, Duration::from_millis(5250));
+    }
+
+    #[test]
+    fn moves_to_go() {
+        let (soft, hard) = deadlines(&clock(10_010, 0, Some(5)));
+        assert_eq!(soft, Duration::from_secs(2));
+        assert_eq!(hard, Duration::from_secs(6));
+
+        // NOTE: last move before time control still keeps reserve
+        let (soft, hard) = deadlines(&clock(10_010, 0, Some(1)));
+        assert_eq!(soft, Duration::from_millis(7500));
+        assert_eq!(hard, Duration::from_millis(7500));
+    }
+
+    #[test]
+    fn never_exceeds_remaining_time() {
+        for time in [0, 5, 10, 11, 50, 100, 1000] {
+            for increment in [0, 100, 10_000] {
+                for moves_to_go in [None, Some(1), Some(2), Some(40)] {
+                    let limits = clock(time, increment, moves_to_go);
+                    let (soft, hard) = deadlines(&limits);
+                    let available = Duration::from_millis(time).saturating_sub(limits.move_overhead);
+
+                    assert!(soft <= hard);
+                    assert!(hard <= available);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn node_limit_aborts() {
+        let limits = SearchLimits { nodes: Some(100), ..SearchLimits::default() };
+        let time_manager = TimeManager::new(&limits, White);
+
+        assert!(!time_manager.should_abort(99));
+        assert!(time_manager.should_abort(100));
+    }
+
+    #[test]
+    fn mate_limit_stops() {
+        let limits = SearchLimits { mate: Some(2), ..SearchLimits::default() };
+        let time_manager = TimeManager::new(&limits, White);
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: SCORE_MATE - 5,
+            depth: 3,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+        assert!(!time_manager.should_stop(&result));
+
+        result.score = SCORE_MATE - 3;
+        assert!(time_manager.should_stop(&result));
+
+        // NOTE: being mated doesn't count
+        result.score = -SCORE_MATE + 2;
+        assert!(!time_manager.should_stop(&result));
+    }
+
+    #[test]
+    fn mate_limit_bounds_depth() {
+        let limits = SearchLimits { mate: Some(3), ..SearchLimits::default() };
+        assert_eq!(TimeManager::new(&limits, White).max_depth(), 6);
+
+        let limits = SearchLimits { mate: Some(3), depth: Some(2), ..SearchLimits::default() };
+        assert_eq!(TimeManager::new(&limits, White).max_depth(), 2);
+
+        let limits = SearchLimits { mate: Some(0), ..SearchLimits::default() };
+        assert_eq!(TimeManager::new(&limits, White).max_depth(), 1);
+    }
+
+    #[test]
+    fn depth_limit_stops() {
+        let time_manager = TimeManager::new(&SearchLimits::depth(3), White);
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            depth: 2,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+        assert!(!time_manager.should_stop(&result));
+
+        result.depth = 3;
+        assert!(time_manager.should_stop(&result));
+    }
+
+    #[test]
+    fn hard_deadline_aborts() {
+        let limits = SearchLimits {
+            movetime: Some(Duration::ZERO),
+            ..SearchLimits::default()
+        };
+        let time_manager = TimeManager::new(&limits, White);
+
+        assert!(time_manager.should_abort(0));
+
+        // NOTE: clock is checked only once per interval
+        assert!(!time_manager.should_abort(1));
+    }
+}
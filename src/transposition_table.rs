@@ -1,4 +1,12 @@
 use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+#[cfg(feature = "transposition_table_stats")]
+use std::sync::atomic::AtomicUsize;
+
+#[cfg(feature = "transposition_table_checks")]
+use std::sync::Mutex;
 
 use crate::prelude::*;
 
@@ -14,8 +22,14 @@ impl ZobristKey {
     }
     
     #[inline(always)]
-    pub const fn index<const BASE: usize>(self) -> usize {
-        (self.0 as usize) % BASE
+    pub const fn index(self, mask: usize) -> usize {
+        (self.0 as usize) & mask
+    }
+
+    // NOTE: high bits are used, cause low ones are already used by `index`
+    #[inline(always)]
+    const fn slot_index(self) -> usize {
+        ((self.0 >> 32) as usize) % BUCKET_SLOTS
     }
 
     #[inline(always)]
@@ -70,256 +84,444 @@ pub const PIECE_SQUARE_TO_HASH: [[u64; 64]; 16] = [
     [0x387b228bb078c476, 0x143f709726369bf, 0x454bc11d8a89b967, 0x22aef8dffe3ab844, 0x33876a35f9788d06, 0x8c38d96c50d860c0, 0x4b15a087a2308fbe, 0x198ca07a1964c5d8, 0xa802349e0cbd48b2, 0xfde744ca29115313, 0x3bdaef9a80d3bd2c, 0xcc5c5a5df8d79140, 0xee11d6f92cfce7cc, 0x7b2dee41eb8b9b48, 0xad06995c0454ec7c, 0x20ccac8232e7a37e, 0xfa3c3eae3134db14, 0xed4183fef120c45, 0x29d9a416536a3ef4, 0xda02622ac5d2027e, 0xa3c801a9a628843d, 0x1f48dd48df03be21, 0x4516b67b94ab79aa, 0x2d7cebfa7391e518, 0x79305e3074006c19, 0x37bd6f1e479b4b6f, 0xbaccaece6d959768, 0x556c2087d106dc39, 0x1b9d7f1137483999, 0xb788949f8cf8d320, 0x8ffdf77a648892f8, 0x815c6a651164d94b, 0xf4d8e34d50077f17, 0x1ff23c80ac73f107, 0xbbf1b301a4bcb0d9, 0xfb6773649924cc79, 0x44350a07dfb4619, 0xc49235de34662571, 0x8dc26c0e33bcd49f, 0xfcacb3a1ba4c6758, 0x96bc2841ab02edb6, 0x1f3bc70aecf53a2e, 0x1d404a306bcadfb5, 0xa91d0a3689738bf2, 0x8e5c6abfa1a16a5, 0x7010dc957d6f9618, 0x4bf20186d38c62fe, 0x7496099910bef2fe, 0xa1c49d195d5b2ddb, 0x24bd97c18a39bff3, 0xe45d2810e8246d11, 0x40cc28cc35467dbb, 0x1d11408ef80a55db, 0xe3b789b6a5feda54, 0x75cd73bcfb0ee8df, 0x3eb495127e8510d8, 0x7e4be3ee3d733018, 0x33e22c4dc1d996cd, 0xb5b6f3e5c386ad5c, 0xda37c1ebd7e7be0c, 0x1902a8a71f614ede, 0x5352d73832e945c7, 0xa4a73a1b2885c24d, 0xf44148aa03dde06d],
 ];
 
-#[derive(Clone)]
-struct Bucket {
-    full_key: ZobristKey,
-    depth: Depth,
+// Data stored in transposition table along with key and depth,
+// perft and search use different payloads, so neither of them
+// pays for fields of another one
+// NOTE: payload is packed into single word to be stored atomically
+pub trait TranspositionPayload: Copy {
+    fn pack(self) -> u64;
+    fn unpack(data: u64) -> Self;
+
+    // NOTE: if it's false, every slot is always-replace one,
+    //       and slot is chosen by key
+    const DEPTH_PREFERRED: bool = true;
+}
+
+// NOTE: perft stores only node count, deep perft entries
+//       are rarely hit, so it's better to keep more shallow ones
+impl TranspositionPayload for usize {
+    #[inline(always)]
+    fn pack(self) -> u64 {
+        self as u64
+    }
+
+    #[inline(always)]
+    fn unpack(data: u64) -> Self {
+        data as usize
+    }
+
+    const DEPTH_PREFERRED: bool = false;
+}
+
+// Number of slots in a bucket, first one is depth-preferred,
+// others are always-replace
+const BUCKET_SLOTS: usize = 2;
+
+// Lockless slot, `check` is xor of key and both data words,
+// so concurrent reader can detect torn writes:
+// https://www.chessprogramming.org/Shared_Hash_Table#Lockless
+// NOTE: slots with all zeros are interpreted as empty,
+//       cause their key is zero
+struct Slot {
+    check: AtomicU64,
 
-    nodes: usize, // TODO: custom buckets
+    // Depth and generation
+    meta: AtomicU64,
+
+    // Packed payload
+    data: AtomicU64,
 
     #[cfg(feature = "transposition_table_checks")]
-    fen: String,
+    fen: Mutex<String>,
 }
 
-impl Bucket {
-    const fn empty() -> Self {
+impl Slot {
+    fn empty() -> Self {
         Self {
-            full_key: ZobristKey(0),
-            depth: 0,
-
-            nodes: 0,
+            check: AtomicU64::new(0),
+            meta: AtomicU64::new(0),
+            data: AtomicU64::new(0),
 
             #[cfg(feature = "transposition_table_checks")]
-            fen: String::new(),
+            fen: Mutex::new(String::new()),
+        }
+    }
+
+    // Returns key, depth, generation and packed payload,
+    // key is wrong if slot was written concurrently
+    #[inline(always)]
+    fn load(&self) -> (ZobristKey, u8, u8, u64) {
+        let check = self.check.load(Ordering::Relaxed);
+        let meta = self.meta.load(Ordering::Relaxed);
+        let data = self.data.load(Ordering::Relaxed);
+
+        (ZobristKey(check ^ meta ^ data), meta as u8, (meta >> 8) as u8, data)
+    }
+
+    #[inline(always)]
+    fn store(&self, full_key: ZobristKey, depth: u8, generation: u8, data: u64) {
+        let meta = depth as u64 | (generation as u64) << 8;
+
+        self.meta.store(meta, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+        self.check.store(full_key.0 ^ meta ^ data, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.check.store(0, Ordering::Relaxed);
+        self.meta.store(0, Ordering::Relaxed);
+        self.data.store(0, Ordering::Relaxed);
+
+        #[cfg(feature = "transposition_table_checks")]
+        self.fen.lock().unwrap().clear();
+    }
+}
+
+struct Bucket {
+    slots: [Slot; BUCKET_SLOTS],
+}
+
+impl Bucket {
+    fn empty() -> Self {
+        Self {
+            slots: std::array::from_fn(|_| Slot::empty()),
         }
     }
 }
 
 #[cfg(feature = "transposition_table_stats")]
 pub struct TranspositionTableStats {
-    writes: usize,
-    rewrites: usize,
+    writes: AtomicUsize,
+    rewrites: AtomicUsize,
     
-    reads: usize,
-    hits: usize,
-    misses: usize,
-    empty_bucket_misses: usize,
-    bucket_condition_misses: usize,
-
-    partial_key_collisions: usize,
-    full_key_collisions: usize,
+    reads: AtomicUsize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    empty_bucket_misses: AtomicUsize,
+    bucket_condition_misses: AtomicUsize,
+
+    partial_key_collisions: AtomicUsize,
+    full_key_collisions: AtomicUsize,
 }
 
 #[cfg(feature = "transposition_table_stats")]
 impl TranspositionTableStats {
     const fn new() -> Self {
         Self {
-            writes: 0,
-            rewrites: 0,
+            writes: AtomicUsize::new(0),
+            rewrites: AtomicUsize::new(0),
 
-            reads: 0,
-            hits: 0,
-            misses: 0,
-            empty_bucket_misses: 0,
-            bucket_condition_misses: 0,
+            reads: AtomicUsize::new(0),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            empty_bucket_misses: AtomicUsize::new(0),
+            bucket_condition_misses: AtomicUsize::new(0),
 
-            partial_key_collisions: 0,
-            full_key_collisions: 0,
+            partial_key_collisions: AtomicUsize::new(0),
+            full_key_collisions: AtomicUsize::new(0),
         }
     }
+
+    #[inline(always)]
+    fn count(counter: &AtomicUsize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 #[cfg(feature = "transposition_table_stats")]
 impl fmt::Debug for TranspositionTableStats {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "- Writes: {}", self.writes)?;
+        let load = |counter: &AtomicUsize| counter.load(Ordering::Relaxed);
+        let writes = load(&self.writes);
+        let reads = load(&self.reads);
+
+        writeln!(f, "- Writes: {}", writes)?;
         writeln!(f, "  - Rewrites: {} ({:.1}%)",
-            self.rewrites,
-            self.rewrites as f64 * 100.0 / self.writes as f64
+            load(&self.rewrites),
+            load(&self.rewrites) as f64 * 100.0 / writes as f64
         )?;
 
         writeln!(f)?;
-        writeln!(f, "- Reads: {}", self.reads)?;
+        writeln!(f, "- Reads: {}", reads)?;
         writeln!(f, "  - Hits: {} ({:.1}%)",
-            self.hits,
-            self.hits as f64 * 100.0 / self.reads as f64,
+            load(&self.hits),
+            load(&self.hits) as f64 * 100.0 / reads as f64,
         )?;
         writeln!(f, "  - Misses: {} ({:.1}%)",
-            self.misses,
-            self.misses as f64 * 100.0 / self.reads as f64,
+            load(&self.misses),
+            load(&self.misses) as f64 * 100.0 / reads as f64,
         )?;
         writeln!(f, "    - Empty bucket: {} ({:.1}%)",
-            self.empty_bucket_misses,
-            self.empty_bucket_misses as f64 * 100.0 / self.reads as f64,
+            load(&self.empty_bucket_misses),
+            load(&self.empty_bucket_misses) as f64 * 100.0 / reads as f64,
         )?;
         writeln!(f, "    - Bucket condition: {} ({:.1}%)",
-            self.bucket_condition_misses,
-            self.bucket_condition_misses as f64 * 100.0 / self.reads as f64,
+            load(&self.bucket_condition_misses),
+            load(&self.bucket_condition_misses) as f64 * 100.0 / reads as f64,
         )?;
 
         writeln!(f, "  - Collisions (partial key): {} ({:.1}%)",
-            self.partial_key_collisions,
-            self.partial_key_collisions as f64 * 100.0 / self.reads as f64,
+            load(&self.partial_key_collisions),
+            load(&self.partial_key_collisions) as f64 * 100.0 / reads as f64,
         )?;
 
         writeln!(f, "  - Collisions (full key): {} ({:.1}%)",
-            self.full_key_collisions,
-            self.full_key_collisions as f64 * 100.0 / self.reads as f64,
+            load(&self.full_key_collisions),
+            load(&self.full_key_collisions) as f64 * 100.0 / reads as f64,
         )?;
 
         Ok(())
     }
 }
 
-// NOTE: instead of using Optional<Bucket>
-//       we interpret buckets with full_key == 0
-//       as empty, saving 1 bit (and 64 bits aligned)
-pub struct TranspositionTable<const SIZE: usize> {
-    buckets: [Bucket; SIZE],
+// NOTE: all operations except resizing and clearing take `&self`,
+//       so single table can be shared between threads
+pub struct TranspositionTable<P: TranspositionPayload> {
+    // NOTE: length is always a power of two,
+    //       so index is just masked key
+    buckets: Box<[Bucket]>,
+    mask: usize,
+
+    // NOTE: slots written before current generation
+    //       are replaced first
+    generation: AtomicU8,
 
     #[cfg(feature = "transposition_table_stats")]
     stats: TranspositionTableStats,
-}
 
-impl<const SIZE: usize> TranspositionTable<SIZE> {
-    #[inline(always)]
-    pub const fn new() -> Self {
-        // NOTE: this method creates TranspositionTable on stack
-        //       so it's not suitable to be used with small tables
-        always!(SIZE <= 1024);
+    payload: PhantomData<P>,
+}
 
-        // NOTE: for performance reasons, we check here for SIZE
-        //       to be power of two
-        always!(SIZE & (SIZE - 1) == 0);
+impl<P: TranspositionPayload> TranspositionTable<P> {
+    // Creates table which takes at most `megabytes` of memory
+    pub fn new(megabytes: usize) -> Self {
+        Self::with_buckets(Self::buckets_for(megabytes))
+    }
 
-        const EMPTY: Bucket = Bucket::empty();
+    // NOTE: `count` is rounded down to a power of two
+    pub fn with_buckets(count: usize) -> Self {
+        let count = Self::round_count(count);
 
         Self {
-            buckets: [EMPTY; SIZE],
+            buckets: Self::allocate(count),
+            mask: count - 1,
+            generation: AtomicU8::new(0),
 
             #[cfg(feature = "transposition_table_stats")]
             stats: TranspositionTableStats::new(),
+
+            payload: PhantomData,
         }
     }
 
-    #[inline(always)]
-    pub fn new_box() -> Box<Self> {
-        always!(SIZE & (SIZE - 1) == 0);
-
-        let mut result: Box<Self> = unsafe { undefined_box() };
-
-        for i in 0..SIZE {
-            unsafe {
-                // NOTE: it's necessary to do all of this magic to replace old
-                //       unitialized value of bucket with empty one and
-                //       do not call `drop` on unitialized memory
-                //       in case `Bucket` needs it
-                #[allow(clippy::forget_non_drop)]
-                std::mem::forget(std::mem::replace(&mut result.buckets[i], Bucket::empty()));
+    fn allocate(count: usize) -> Box<[Bucket]> {
+        (0..count).map(|_| Bucket::empty()).collect()
+    }
+
+    // NOTE: it drops all entries, like `clear` does
+    pub fn resize(&mut self, megabytes: usize) {
+        let count = Self::round_count(Self::buckets_for(megabytes));
+        if count == self.buckets.len() {
+            self.clear();
+            return;
+        }
+
+        // NOTE: old table is dropped first, so peak memory usage
+        //       isn't a sum of old and new sizes
+        self.buckets = Box::new([]);
+        self.buckets = Self::allocate(count);
+        self.mask = count - 1;
+        self.generation = AtomicU8::new(0);
+
+        #[cfg(feature = "transposition_table_stats")]
+        {
+            self.stats = TranspositionTableStats::new();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in self.buckets.iter() {
+            for slot in &bucket.slots {
+                slot.clear();
             }
         }
 
+        self.generation = AtomicU8::new(0);
+
         #[cfg(feature = "transposition_table_stats")]
         {
-            result.stats = TranspositionTableStats::new();
+            self.stats = TranspositionTableStats::new();
         }
+    }
 
-        result
+    // Should be called before every search,
+    // so entries of previous searches are replaced first
+    #[inline(always)]
+    pub fn new_generation(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
     }
 
+    // Number of buckets
     #[inline(always)]
-    pub fn add(&mut self, board: &Board, depth: Depth, nodes: usize) {
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    #[inline(always)]
+    const fn buckets_for(megabytes: usize) -> usize {
+        megabytes * 1024 * 1024 / std::mem::size_of::<Bucket>()
+    }
+
+    #[inline(always)]
+    const fn round_count(count: usize) -> usize {
+        if count <= 1 {
+            return 1;
+        }
+
+        1 << (usize::BITS - 1 - count.leading_zeros())
+    }
+
+    #[inline(always)]
+    pub fn add(&self, board: &Board, depth: Depth, payload: P) {
+        always!(depth <= u8::MAX as Depth);
+
         let full_key = board.hash();
-        let small_key = full_key.index::<SIZE>();
-        always!(small_key < SIZE);
+        let small_key = full_key.index(self.mask);
+        always!(small_key < self.len());
+
+        let generation = self.generation.load(Ordering::Relaxed);
+        let bucket = &self.buckets[small_key];
+
+        // NOTE: depth-preferred slot keeps deep entries of current search,
+        //       everything else goes to always-replace slot
+        let slot_index = if P::DEPTH_PREFERRED {
+            let (deep_key, deep_depth, deep_generation, _) = bucket.slots[0].load();
+
+            if deep_key.empty() ||
+               deep_generation != generation ||
+               depth >= deep_depth as Depth
+            {
+                0
+            } else {
+                1
+            }
+        } else {
+            full_key.slot_index()
+        };
+
+        let slot = &bucket.slots[slot_index];
 
         #[cfg(feature = "transposition_table_stats")]
         {
-            self.stats.writes += 1;
+            TranspositionTableStats::count(&self.stats.writes);
 
-            if !self.buckets[small_key].full_key.empty() {
-                self.stats.rewrites += 1;
+            if !slot.load().0.empty() {
+                TranspositionTableStats::count(&self.stats.rewrites);
             }
         }
 
         #[cfg(feature = "transposition_table_checks")]
         {
-            self.buckets[small_key].fen = board.debug_fen();
+            *slot.fen.lock().unwrap() = board.debug_fen();
         }
-        
-        self.buckets[small_key].full_key = full_key;
-        self.buckets[small_key].depth = depth;
-        self.buckets[small_key].nodes = nodes;
+
+        slot.store(full_key, depth as u8, generation, payload.pack());
+    }
+
+    // Finds entry of exactly the same depth, used by perft
+    #[inline(always)]
+    pub fn get(&self, board: &Board, depth: Depth) -> Option<P> {
+        let (slot_depth, payload) = self.find(board, |slot_depth| slot_depth == depth)?;
+        always!(slot_depth == depth);
+
+        Some(payload)
     }
 
-    // TODO: return bucket
+    // Finds entry of any depth, depth-preferred slot goes first
     #[inline(always)]
-    pub fn get(&mut self, board: &Board, depth: Depth) -> Option<usize> {
+    pub fn probe(&self, board: &Board) -> Option<(Depth, P)> {
+        self.find(board, |_| true)
+    }
+
+    #[inline(always)]
+    fn find(
+        &self,
+        board: &Board,
+        condition: impl Fn(Depth) -> bool,
+    ) -> Option<(Depth, P)> {
         let full_key = board.hash();
-        let small_key = full_key.index::<SIZE>();
-        always!(small_key < SIZE);
+        let small_key = full_key.index(self.mask);
+        always!(small_key < self.len());
 
         #[cfg(feature = "transposition_table_stats")]
-        {
-            self.stats.reads += 1;
-        }
+        TranspositionTableStats::count(&self.stats.reads);
 
         let bucket = &self.buckets[small_key];
-        if bucket.full_key != full_key {
-            // TODO: move to method
-            #[cfg(feature = "transposition_table_stats")]
-            {
-                if bucket.full_key.empty() {
-                    self.stats.empty_bucket_misses += 1;
-                } else {
-                    self.stats.partial_key_collisions += 1;
-                }
-                
-                self.stats.misses += 1;
-            }
 
-            return None;
-        }
+        #[cfg(feature = "transposition_table_stats")]
+        let mut key_found = false;
+
+        for slot in &bucket.slots {
+            let (slot_key, slot_depth, _, data) = slot.load();
+            if slot_key != full_key {
+                continue;
+            }
 
-        if bucket.depth != depth {
             #[cfg(feature = "transposition_table_stats")]
             {
-                self.stats.bucket_condition_misses += 1;
-                self.stats.misses += 1;
+                key_found = true;
             }
 
-            return None;
-        }
-
-        #[cfg(feature = "transposition_table_checks")]
-        {
-            let board_fen = board.debug_fen();
+            if !condition(slot_depth as Depth) {
+                continue;
+            }
 
-            if board_fen != bucket.fen {
-                #[cfg(feature = "transposition_table_stats")]
-                {
-                    self.stats.full_key_collisions += 1;
-                    self.stats.misses += 1;
+            #[cfg(feature = "transposition_table_checks")]
+            {
+                if board.debug_fen() != *slot.fen.lock().unwrap() {
+                    #[cfg(feature = "transposition_table_stats")]
+                    {
+                        TranspositionTableStats::count(&self.stats.full_key_collisions);
+                        TranspositionTableStats::count(&self.stats.misses);
+                    }
+
+                    return None;
                 }
-
-                return None;
             }
+
+            #[cfg(feature = "transposition_table_stats")]
+            TranspositionTableStats::count(&self.stats.hits);
+
+            return Some((slot_depth as Depth, P::unpack(data)));
         }
 
+        // TODO: move to method
         #[cfg(feature = "transposition_table_stats")]
         {
-            self.stats.hits += 1;
+            if key_found {
+                TranspositionTableStats::count(&self.stats.bucket_condition_misses);
+            } else if bucket.slots.iter().all(|slot| slot.load().0.empty()) {
+                TranspositionTableStats::count(&self.stats.empty_bucket_misses);
+            } else {
+                TranspositionTableStats::count(&self.stats.partial_key_collisions);
+            }
+
+            TranspositionTableStats::count(&self.stats.misses);
         }
 
-        Some(bucket.nodes)
+        None
     }
 
     #[cfg(feature = "transposition_table_stats")]
     pub fn print_statistics(&self) {
         println!("{:?}", self.stats);
 
-        let size = SIZE;
-        let non_empty = self.buckets.iter().filter(|b| !b.full_key.empty()).count();
+        let size = self.len() * BUCKET_SLOTS;
+        let non_empty = self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.slots.iter())
+            .filter(|slot| !slot.load().0.empty())
+            .count();
 
         println!(
             "Fullness: {} / {} ({:.1}%)",
@@ -336,7 +538,7 @@ mod tests {
 
     #[test]
     fn basic_usage() {
-        let mut tt = TranspositionTable::<1024>::new();
+        let tt = TranspositionTable::<usize>::with_buckets(1024);
         let board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
 
         assert_eq!(tt.get(&board, 3), None);
@@ -346,6 +548,105 @@ mod tests {
         assert_eq!(tt.get(&board, 4), None);
     }
 
+    #[test]
+    fn size_is_power_of_two() {
+        assert_eq!(TranspositionTable::<usize>::with_buckets(0).len(), 1);
+        assert_eq!(TranspositionTable::<usize>::with_buckets(1000).len(), 512);
+        assert_eq!(TranspositionTable::<usize>::with_buckets(1024).len(), 1024);
+
+        let tt = TranspositionTable::<usize>::new(1);
+        assert!(tt.len().is_power_of_two());
+        assert!(tt.len() * std::mem::size_of::<Bucket>() <= 1024 * 1024);
+    }
+
+    #[test]
+    fn clear_and_resize() {
+        let mut tt = TranspositionTable::<usize>::new(1);
+        let board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        tt.add(&board, 3, 10);
+        tt.clear();
+        assert_eq!(tt.get(&board, 3), None);
+
+        let len = tt.len();
+        tt.add(&board, 3, 10);
+        tt.resize(2);
+        assert_eq!(tt.len(), len * 2);
+        assert_eq!(tt.get(&board, 3), None);
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Payload(usize);
+
+    impl TranspositionPayload for Payload {
+        fn pack(self) -> u64 {
+            self.0 as u64
+        }
+
+        fn unpack(data: u64) -> Self {
+            Self(data as usize)
+        }
+    }
+
+    #[test]
+    fn depth_preferred_replacement() {
+        // NOTE: single bucket, so every position collides
+        let tt = TranspositionTable::<Payload>::with_buckets(1);
+        let board_1 = Board::from_fen(b"4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        let board_2 = Board::from_fen(b"4k3/8/8/8/8/8/8/3K4 w - - 0 1");
+        let board_3 = Board::from_fen(b"4k3/8/8/8/8/8/8/2K5 w - - 0 1");
+
+        tt.add(&board_1, 5, Payload(1));
+        tt.add(&board_2, 3, Payload(2));
+        tt.add(&board_3, 2, Payload(3));
+
+        // NOTE: shallow entries replace each other, deep one stays
+        assert_eq!(tt.probe(&board_1), Some((5, Payload(1))));
+        assert_eq!(tt.probe(&board_2), None);
+        assert_eq!(tt.probe(&board_3), Some((2, Payload(3))));
+
+        tt.add(&board_2, 6, Payload(4));
+        assert_eq!(tt.probe(&board_1), None);
+        assert_eq!(tt.probe(&board_2), Some((6, Payload(4))));
+        assert_eq!(tt.get(&board_2, 5), None);
+        assert_eq!(tt.get(&board_2, 6), Some(Payload(4)));
+
+        // NOTE: entries of previous searches are replaced first
+        tt.new_generation();
+        tt.add(&board_1, 1, Payload(5));
+        assert_eq!(tt.probe(&board_1), Some((1, Payload(5))));
+        assert_eq!(tt.probe(&board_2), None);
+    }
+
+    #[test]
+    fn concurrent_access() {
+        // NOTE: small table, so threads overwrite each other all the time
+        let tt = TranspositionTable::<usize>::with_buckets(16);
+        let mut rng = FastRng::from_system_time();
+        let boards: Vec<Board> = (0..64).map(|_| Board::rand(&mut rng)).collect();
+
+        std::thread::scope(|scope| {
+            for thread_index in 0..4 {
+                let tt = &tt;
+                let boards = &boards;
+
+                scope.spawn(move || {
+                    for i in 0..10_000 {
+                        let board = &boards[(i * 7 + thread_index) % boards.len()];
+                        let nodes = board.hash().0 as usize;
+
+                        tt.add(board, 1, nodes);
+
+                        // NOTE: torn entries are never returned
+                        if let Some(found) = tt.get(board, 1) {
+                            assert_eq!(found, nodes);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
     #[test]
     fn remove_piece_affects_hash() {
         let mut board = Board::from_fen(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
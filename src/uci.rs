@@ -0,0 +1,830 @@
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::prelude::*;
+
+#[cfg(feature = "perft")]
+use crate::perft;
+
+const ENGINE_NAME: &str = "Marcus";
+const ENGINE_AUTHOR: &str = "codingjerk";
+
+const MAX_MOVE_OVERHEAD: u64 = 5000; // ms
+
+const MAX_HASH_SIZE: usize = 4096; // MB
+
+const MAX_THREADS: usize = 256;
+
+const STARTPOS_FEN: &[u8] = b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// NOTE: halfmove clock and fullmove counter are optional in positions
+//       sent by some GUIs, so we fill them with default values
+const FEN_DEFAULT_FIELDS: [&str; 6] = ["", "", "", "", "0", "1"];
+
+pub struct Uci<W: Write + Send + 'static = io::Stdout> {
+    board: Board,
+    movegen: MoveGenerator,
+
+    // NOTE: search runs in its own thread, so commands
+    //       are read while it's running
+    searcher: Arc<Mutex<Searcher>>,
+    search_thread: Option<JoinHandle<()>>,
+
+    // NOTE: set on `stop` and `quit` to abort current search,
+    //       see `Searcher::abort`
+    abort: Arc<AtomicBool>,
+
+    // NOTE: shared with search thread, which prints info and bestmove
+    output: Arc<Mutex<W>>,
+
+    // NOTE: only changes castling notation,
+    //       Chess960 positions are supported anyway
+    chess960: bool,
+
+    move_overhead: Duration,
+}
+
+impl Uci {
+    pub fn new() -> Self {
+        Self::with_output(io::stdout())
+    }
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+    pub fn with_output(output: W) -> Self {
+        let searcher = Searcher::new();
+
+        Self {
+            board: Board::from_fen(STARTPOS_FEN),
+            movegen: MoveGenerator::new(),
+            abort: searcher.abort_flag(),
+            searcher: Arc::new(Mutex::new(searcher)),
+            search_thread: None,
+            output: Arc::new(Mutex::new(output)),
+            chess960: false,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+        }
+    }
+
+    pub fn run(&mut self) {
+        let stdin = io::stdin();
+
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+
+            match self.execute(&line) {
+                Ok(true) => {},
+                _ => break,
+            }
+        }
+    }
+
+    // Executes single command, returns `false` if engine should quit
+    pub fn execute(&mut self, command: &str) -> io::Result<bool> {
+        let mut tokens = command.split_ascii_whitespace();
+
+        match tokens.next() {
+            Some("uci") => self.uci()?,
+            Some("isready") => writeln!(self.output(), "readyok")?,
+            Some("ucinewgame") => {
+                self.wait_for_search();
+                self.board = Board::from_fen(STARTPOS_FEN);
+                self.searcher().clear_hash();
+            },
+            Some("setoption") => self.setoption(tokens),
+            Some("position") => self.position(tokens),
+            Some("go") => self.go(tokens)?,
+            Some("quit") => {
+                self.stop_search();
+                return Ok(false);
+            },
+
+            Some("stop") => self.stop_search(),
+
+            // NOTE: protocol requires to ignore unknown commands
+            _ => {},
+        }
+
+        self.output().flush()?;
+
+        Ok(true)
+    }
+
+    #[inline(always)]
+    fn output(&self) -> MutexGuard<'_, W> {
+        self.output.lock().unwrap()
+    }
+
+    // NOTE: it's locked by search thread while search is running
+    #[inline(always)]
+    fn searcher(&self) -> MutexGuard<'_, Searcher> {
+        self.searcher.lock().unwrap()
+    }
+
+    // Blocks until current search (if any) is over and bestmove is sent
+    fn wait_for_search(&mut self) {
+        if let Some(search_thread) = self.search_thread.take() {
+            if let Err(panic) = search_thread.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+    }
+
+    // NOTE: search thread can be parked in infinite mode,
+    //       so it's woken up to send bestmove
+    fn stop_search(&mut self) {
+        self.abort.store(true, Ordering::Relaxed);
+
+        if let Some(search_thread) = &self.search_thread {
+            search_thread.thread().unpark();
+        }
+
+        self.wait_for_search();
+    }
+
+    fn uci(&self) -> io::Result<()> {
+        let mut output = self.output();
+
+        writeln!(output, "id name {} {}", ENGINE_NAME, env!("CARGO_PKG_VERSION"))?;
+        writeln!(output, "id author {}", ENGINE_AUTHOR)?;
+        writeln!(output, "option name Hash type spin default {DEFAULT_HASH_SIZE} min 1 max {MAX_HASH_SIZE}")?;
+        writeln!(output, "option name Threads type spin default 1 min 1 max {MAX_THREADS}")?;
+        writeln!(output, "option name UCI_Chess960 type check default false")?;
+        writeln!(
+            output,
+            "option name Move Overhead type spin default {} min 0 max {MAX_MOVE_OVERHEAD}",
+            DEFAULT_MOVE_OVERHEAD.as_millis(),
+        )?;
+        writeln!(output, "uciok")
+    }
+
+    // NOTE: option names are case insensitive, values are not
+    // NOTE: options can't be changed during search,
+    //       so it's waited for, like GUIs should do
+    fn setoption<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) {
+        if tokens.next() != Some("name") {
+            return;
+        }
+
+        self.wait_for_search();
+
+        let name: Vec<&str> = tokens.by_ref().take_while(|&token| token != "value").collect();
+        let value = tokens.next();
+
+        let name = name.join(" ");
+        if name.eq_ignore_ascii_case("Hash") {
+            if let Some(Ok(megabytes)) = value.map(str::parse::<usize>) {
+                self.searcher().resize_hash(megabytes.clamp(1, MAX_HASH_SIZE));
+            }
+        } else if name.eq_ignore_ascii_case("Threads") {
+            if let Some(Ok(threads)) = value.map(str::parse::<usize>) {
+                self.searcher().set_threads(threads.clamp(1, MAX_THREADS));
+            }
+        } else if name.eq_ignore_ascii_case("UCI_Chess960") {
+            match value {
+                Some("true") => self.chess960 = true,
+                Some("false") => self.chess960 = false,
+                _ => {},
+            }
+        } else if name.eq_ignore_ascii_case("Move Overhead") {
+            if let Some(Ok(milliseconds)) = value.map(str::parse::<u64>) {
+                self.move_overhead = Duration::from_millis(milliseconds.min(MAX_MOVE_OVERHEAD));
+            }
+        }
+    }
+
+    fn position<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) {
+        let board = match tokens.next() {
+            Some("startpos") => {
+                // Skip `moves` token
+                tokens.next();
+
+                Board::from_fen(STARTPOS_FEN)
+            },
+            Some("fen") => {
+                let mut fields: Vec<&str> = tokens
+                    .by_ref()
+                    .take_while(|&token| token != "moves")
+                    .collect();
+
+                if fields.len() < 4 || fields.len() > 6 {
+                    return;
+                }
+
+                fields.extend_from_slice(&FEN_DEFAULT_FIELDS[fields.len()..]);
+
+                match Board::try_from_fen(fields.join(" ").as_bytes()) {
+                    Ok(board) => board,
+                    Err(_) => return,
+                }
+            },
+            _ => return,
+        };
+
+        self.board = board;
+
+        for token in tokens {
+            let chess_move = match Move::from_uci(&self.board, token, self.chess960) {
+                Ok(chess_move) => chess_move,
+                Err(_) => break,
+            };
+
+            self.movegen.make_move_unchecked(&mut self.board, chess_move);
+            self.board.reset_ply();
+        }
+    }
+
+    fn go<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) -> io::Result<()> {
+        let mut limits = SearchLimits {
+            move_overhead: self.move_overhead,
+            ..SearchLimits::default()
+        };
+        let mut infinite = false;
+
+        // NOTE: parameters with bad values are skipped,
+        //       GUI waits for bestmove after any `go` anyway
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => if let Some(Ok(value)) = tokens.next().map(str::parse) {
+                    limits.depth = Some(value);
+                },
+                "nodes" => if let Some(Ok(value)) = tokens.next().map(str::parse) {
+                    limits.nodes = Some(value);
+                },
+                "mate" => if let Some(Ok(value)) = tokens.next().map(str::parse) {
+                    limits.mate = Some(value);
+                },
+                "movestogo" => if let Some(Ok(value)) = tokens.next().map(str::parse) {
+                    limits.moves_to_go = Some(value);
+                },
+                "movetime" => if let Some(value) = parse_milliseconds(tokens.next()) {
+                    limits.movetime = Some(value);
+                },
+                "wtime" => if let Some(value) = parse_milliseconds(tokens.next()) {
+                    limits.time[White.index() as usize] = Some(value);
+                },
+                "btime" => if let Some(value) = parse_milliseconds(tokens.next()) {
+                    limits.time[Black.index() as usize] = Some(value);
+                },
+                "winc" => if let Some(value) = parse_milliseconds(tokens.next()) {
+                    limits.increment[White.index() as usize] = value;
+                },
+                "binc" => if let Some(value) = parse_milliseconds(tokens.next()) {
+                    limits.increment[Black.index() as usize] = value;
+                },
+
+                #[cfg(feature = "perft")]
+                "perft" => if let Some(Ok(value)) = tokens.next().map(str::parse) {
+                    self.wait_for_search();
+                    return self.go_perft(value);
+                },
+
+                // NOTE: search without limits goes on until it's aborted,
+                //       `infinite` also keeps bestmove until `stop`
+                "infinite" => infinite = true,
+
+                // NOTE: `ponder` and `searchmoves` aren't supported yet
+                _ => {},
+            }
+        }
+
+        self.start_search(limits, infinite);
+
+        Ok(())
+    }
+
+    // Searches copy of current position in its own thread,
+    // which prints bestmove when search is over
+    fn start_search(&mut self, limits: SearchLimits, infinite: bool) {
+        self.wait_for_search();
+        self.abort.store(false, Ordering::Relaxed);
+
+        let mut board = self.board.clone();
+        let searcher = Arc::clone(&self.searcher);
+        let abort = Arc::clone(&self.abort);
+        let output = Arc::clone(&self.output);
+        let chess960 = self.chess960;
+
+        self.search_thread = Some(thread::spawn(move || {
+            let mut searcher = searcher.lock().unwrap();
+            let wait_for_stop = || {
+                // NOTE: protocol forbids bestmove in infinite mode before `stop`,
+                //       even if search is over (like when mate is found)
+                while infinite && !abort.load(Ordering::Relaxed) {
+                    thread::park();
+                }
+            };
+
+            // NOTE: errors can't be returned from this thread,
+            //       and there is nobody to report them if GUI is gone
+            let _ = search(&mut searcher, &mut board, &limits, &output, chess960, wait_for_stop);
+        }));
+    }
+
+    #[cfg(feature = "perft")]
+    fn go_perft(&self, depth: usize) -> io::Result<()> {
+        let mut output = self.output();
+
+        if depth == 0 {
+            return writeln!(output, "Nodes searched: 1");
+        }
+
+        // NOTE: output is the same as Stockfish's one,
+        //       so results can be compared with simple diff
+        let transposition_table = perft::perft_transposition_table(depth);
+        let divided = perft::perft_divide(
+            &self.board,
+            depth,
+            perft::PerftThreads::default(),
+            &transposition_table,
+        );
+        for &(chess_move, nodes) in &divided {
+            writeln!(output, "{}: {nodes}", chess_move.to_uci(self.chess960))?;
+        }
+
+        let nodes: usize = divided.iter().map(|&(_, nodes)| nodes).sum();
+        writeln!(output)?;
+        writeln!(output, "Nodes searched: {nodes}")
+    }
+}
+
+impl<W: Write + Send + 'static> Drop for Uci<W> {
+    fn drop(&mut self) {
+        self.stop_search();
+    }
+}
+
+fn search(
+    searcher: &mut Searcher,
+    board: &mut Board,
+    limits: &SearchLimits,
+    output: &Mutex<impl Write>,
+    chess960: bool,
+    before_bestmove: impl FnOnce(),
+) -> io::Result<()> {
+    let start = Instant::now();
+    let mut report_result = Ok(());
+    let result = searcher.search(board, limits, |result| {
+        if report_result.is_ok() {
+            let mut output = output.lock().unwrap();
+            report_result = write_info(&mut *output, result, start, chess960)
+                .and_then(|_| output.flush());
+        }
+    });
+    report_result?;
+
+    before_bestmove();
+
+    let mut output = output.lock().unwrap();
+    match result.best_move {
+        Some(chess_move) => writeln!(output, "bestmove {}", chess_move.to_uci(chess960))?,
+        None => writeln!(output, "bestmove 0000")?,
+    }
+
+    output.flush()
+}
+
+// NOTE: negative clocks are sent by some GUIs when time is up,
+//       they're treated as zero
+fn parse_milliseconds(token: Option<&str>) -> Option<Duration> {
+    let value: i64 = token?.parse().ok()?;
+
+    Some(Duration::from_millis(value.max(0) as u64))
+}
+
+fn write_info(
+    output: &mut impl Write,
+    result: &SearchResult,
+    start: Instant,
+    chess960: bool,
+) -> io::Result<()> {
+    let elapsed = start.elapsed();
+    let nps = (result.nodes as f64 / elapsed.as_secs_f64().max(1e-3)) as usize;
+
+    write!(output, "info depth {} score ", result.depth)?;
+
+    if result.score >= SCORE_MATE_BOUND {
+        write!(output, "mate {}", (SCORE_MATE - result.score + 1) / 2)?;
+    } else if result.score <= -SCORE_MATE_BOUND {
+        write!(output, "mate {}", -(SCORE_MATE + result.score) / 2)?;
+    } else {
+        write!(output, "cp {}", result.score)?;
+    }
+
+    write!(
+        output,
+        " nodes {} nps {} time {} pv",
+        result.nodes,
+        nps,
+        elapsed.as_millis(),
+    )?;
+
+    for &chess_move in &result.pv {
+        write!(output, " {}", chess_move.to_uci(chess960))?;
+    }
+
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NOTE: search is waited for, so its output is complete
+    fn execute(uci: &mut Uci<Vec<u8>>, commands: &[&str]) -> String {
+        for command in commands {
+            let running = uci.execute(command).unwrap();
+            assert!(running);
+        }
+
+        uci.wait_for_search();
+        let output = std::mem::take(&mut *uci.output());
+
+        String::from_utf8(output).unwrap()
+    }
+
+    fn fen(uci: &Uci<Vec<u8>>) -> String {
+        let mut buffer = FenBuffer::new();
+        uci.board.fen(&mut buffer);
+
+        String::from(buffer.as_str())
+    }
+
+    #[test]
+    fn handshake() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["uci"]);
+
+        assert!(output.starts_with("id name Marcus"));
+        assert!(output.contains("id author"));
+        assert!(output.ends_with("uciok\n"));
+    }
+
+    #[test]
+    fn hash_option() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["uci"]);
+        assert!(output.contains("option name Hash type spin default 16 min 1 max 4096"));
+
+        let output = execute(&mut uci, &[
+            "setoption name Hash value 1",
+            "ucinewgame",
+            "position startpos",
+            "go depth 2",
+        ]);
+        assert!(output.contains("bestmove"));
+    }
+
+    #[test]
+    fn castling_notation() {
+        let mut uci = Uci::with_output(Vec::new());
+        let position = "position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves";
+
+        execute(&mut uci, &[&format!("{position} e1g1 e8c8")]);
+        assert!(fen(&uci).starts_with("2kr3r/8/8/8/8/8/8/R4RK1 w - - "));
+
+        // NOTE: king to rook is illegal king move in standard mode
+        execute(&mut uci, &[&format!("{position} e1h1")]);
+        assert!(fen(&uci).starts_with("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - "));
+    }
+
+    #[test]
+    fn chess960_castling_notation() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["uci"]);
+        assert!(output.contains("option name UCI_Chess960 type check default false"));
+
+        execute(&mut uci, &[
+            "setoption name UCI_Chess960 value true",
+            "position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1h1 e8a8",
+        ]);
+        assert!(fen(&uci).starts_with("2kr3r/8/8/8/8/8/8/R4RK1 w - - "));
+
+        // NOTE: king which stays on its square castles too
+        execute(&mut uci, &["position fen 1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1 moves g1h1"]);
+        assert!(fen(&uci).starts_with("1r4kr/8/8/8/8/8/8/1R3RK1 b kq - "));
+
+        let output = execute(&mut uci, &["go perft 1"]);
+        assert!(output.contains("g8h8: 1"));
+
+        // NOTE: rook on f1 attacks the square king crosses
+        assert!(!output.contains("g8b8"));
+    }
+
+    #[test]
+    fn isready() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["isready"]);
+
+        assert_eq!(output, "readyok\n");
+    }
+
+    #[test]
+    fn quit() {
+        let mut uci = Uci::with_output(Vec::new());
+
+        assert!(!uci.execute("quit").unwrap());
+    }
+
+    #[test]
+    fn unknown_commands_are_ignored() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["", "   ", "hello world", "stop"]);
+
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn position_startpos() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position startpos"]);
+
+        assert_eq!(fen(&uci), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    }
+
+    #[test]
+    fn position_startpos_moves() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position startpos moves e2e4 c7c5 g1f3"]);
+
+        assert_eq!(fen(&uci), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+    }
+
+    #[test]
+    fn position_fen_moves() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position fen 8/4P3/8/8/8/8/k7/4K3 w - - 0 1 moves e7e8q a2b2 e1e2"]);
+
+        assert!(fen(&uci).starts_with("4Q3/8/8/8/8/8/1k2K3/8 b - - "));
+    }
+
+    #[test]
+    fn position_fen_without_clocks() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position fen 4k3/8/8/8/8/8/8/R3K3 w Q - moves e1c1"]);
+
+        assert_eq!(fen(&uci), "4k3/8/8/8/8/8/8/2KR4 b - - 1 1");
+    }
+
+    #[test]
+    fn position_ignores_invalid_fen() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &[
+            "position startpos moves e2e4",
+            "position fen 4k3/8/8/8/8/8/8/R3X3 w Q - 0 1",
+            "position fen 4k3/8/8/8/8/8/8/R7 w - - 0 1",
+            "position fen 4k3/8/8/8/8/8/8/R3K3 w Q e3 0 1",
+        ]);
+
+        assert_eq!(fen(&uci), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    }
+
+    #[test]
+    fn position_stops_at_illegal_move() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position startpos moves e2e4 e2e4 d7d5"]);
+
+        assert_eq!(fen(&uci), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    }
+
+    #[test]
+    fn position_many_moves() {
+        let mut moves = String::from("position startpos moves");
+        for _ in 0..100 {
+            moves.push_str(" g1f3 g8f6 f3g1 f6g8");
+        }
+
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &[&moves]);
+
+        assert_eq!(fen(&uci), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 400 201");
+    }
+
+    #[test]
+    fn position_moves_count_for_repetition() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8"]);
+
+        assert!(uci.board.is_threefold_repetition());
+    }
+
+    #[test]
+    fn ucinewgame_resets_position() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position startpos moves e2e4", "ucinewgame"]);
+
+        assert_eq!(fen(&uci), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    }
+
+    #[test]
+    fn go_returns_legal_move() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position fen 4k3/8/8/8/8/8/3r4/r3K3 w - - 0 1", "go depth 2"]);
+
+        assert!(output.ends_with("bestmove e1d2\n"));
+    }
+
+    #[test]
+    fn go_depth_reports_info() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position startpos", "go depth 3"]);
+        let lines: Vec<_> = output.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("info depth 1 score cp "));
+        assert!(lines[1].starts_with("info depth 2 score cp "));
+        assert!(lines[2].starts_with("info depth 3 score cp "));
+        assert!(lines[3].starts_with("bestmove "));
+    }
+
+    #[test]
+    fn go_reports_mate() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position fen k7/8/2K5/8/8/8/8/7R w - - 0 1", "go depth 5"]);
+
+        assert!(output.contains("score mate 2 "));
+    }
+
+    #[test]
+    fn go_reports_being_mated() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position fen k7/8/2K5/8/8/8/8/7R w - - 0 1 moves c6b6", "go depth 5"]);
+
+        assert!(output.contains("score mate -1 "));
+    }
+
+    #[test]
+    fn threads_option() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["uci"]);
+        assert!(output.contains("option name Threads type spin default 1 min 1 max 256\n"));
+
+        execute(&mut uci, &["setoption name Threads value 3"]);
+        assert_eq!(uci.searcher().threads(), 3);
+
+        let output = execute(&mut uci, &["position startpos", "go depth 3"]);
+        assert!(output.contains("info depth 3 "));
+        assert!(output.contains("bestmove "));
+
+        execute(&mut uci, &["setoption name threads value 0"]);
+        assert_eq!(uci.searcher().threads(), 1);
+    }
+
+    #[test]
+    fn move_overhead_option() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["uci"]);
+        assert!(output.contains("option name Move Overhead type spin default 10 min 0 max 5000\n"));
+
+        execute(&mut uci, &["setoption name Move Overhead value 100"]);
+        assert_eq!(uci.move_overhead, Duration::from_millis(100));
+
+        execute(&mut uci, &["setoption name move overhead value 100000"]);
+        assert_eq!(uci.move_overhead, Duration::from_millis(MAX_MOVE_OVERHEAD));
+    }
+
+    #[test]
+    fn go_movetime() {
+        let mut uci = Uci::with_output(Vec::new());
+        let start = Instant::now();
+        let output = execute(&mut uci, &["position startpos", "go movetime 50"]);
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(output.contains("bestmove "));
+        assert!(!output.contains("bestmove 0000"));
+    }
+
+    #[test]
+    fn go_clock() {
+        let mut uci = Uci::with_output(Vec::new());
+        let start = Instant::now();
+        let output = execute(&mut uci, &[
+            "position startpos moves e2e4",
+            "go wtime 1 winc 0 btime 300 binc 10 movestogo 20",
+        ]);
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(output.contains("bestmove "));
+
+        // NOTE: GUIs can send negative time when clock is flagged
+        let output = execute(&mut uci, &["go wtime -5 btime -5"]);
+        assert!(output.contains("info depth 1 "));
+        assert!(output.contains("bestmove "));
+    }
+
+    #[test]
+    fn go_nodes() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position startpos", "go nodes 2000"]);
+
+        assert!(output.contains("info depth 1 "));
+        assert!(output.contains("bestmove "));
+    }
+
+    #[test]
+    fn go_mate() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "go mate 1"]);
+
+        assert!(output.contains("score mate 1 "));
+        assert!(output.ends_with("bestmove a1a8\n"));
+    }
+
+    #[test]
+    fn go_without_limits_is_aborted_on_quit() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position startpos"]);
+
+        // NOTE: commands are read while search is running
+        assert!(uci.execute("go").unwrap());
+        thread::sleep(Duration::from_millis(50));
+        assert!(uci.execute("isready").unwrap());
+        assert!(!uci.execute("quit").unwrap());
+
+        let output = String::from_utf8(std::mem::take(&mut *uci.output())).unwrap();
+        let readyok = output.find("readyok\n").unwrap();
+        let bestmove = output.find("bestmove ").unwrap();
+        assert!(readyok < bestmove);
+        assert!(output.contains("info depth 1 "));
+        assert!(!output.contains("bestmove 0000"));
+    }
+
+    #[test]
+    fn go_infinite_is_stopped() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position startpos"]);
+
+        assert!(uci.execute("go infinite").unwrap());
+        thread::sleep(Duration::from_millis(50));
+
+        let output = execute(&mut uci, &["stop"]);
+        assert!(output.contains("info depth 1 "));
+        assert!(output.contains("bestmove "));
+        assert!(!output.contains("bestmove 0000"));
+    }
+
+    #[test]
+    fn go_infinite_waits_for_stop() {
+        let mut uci = Uci::with_output(Vec::new());
+        execute(&mut uci, &["position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1"]);
+
+        // NOTE: mate is found at once, but search is infinite
+        assert!(uci.execute("go infinite").unwrap());
+        thread::sleep(Duration::from_millis(50));
+
+        let output = String::from_utf8(uci.output().clone()).unwrap();
+        assert!(output.contains("score mate 1 "));
+        assert!(!output.contains("bestmove"));
+
+        let output = execute(&mut uci, &["stop"]);
+        assert!(output.ends_with("bestmove a1a8\n"));
+    }
+
+    #[test]
+    fn go_ignores_bad_limits() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position startpos", "go wtime abc depth 2"]);
+        assert!(output.contains("info depth 2 "));
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+
+        let output = execute(&mut uci, &["go movetime 50 depth"]);
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn go_without_legal_moves() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position fen 8/8/8/8/8/4k3/4q3/4K3 w - - 0 1", "go depth 2"]);
+
+        assert!(output.contains("score mate 0 "));
+        assert!(output.ends_with("bestmove 0000\n"));
+    }
+
+    #[cfg(feature = "perft")]
+    #[test]
+    fn go_perft() {
+        let mut uci = Uci::with_output(Vec::new());
+        let output = execute(&mut uci, &["position startpos", "go perft 3"]);
+
+        assert!(output.contains("e2e4: 600\n"));
+        assert!(output.contains("g1f3: 440\n"));
+        assert_eq!(output.lines().filter(|line| line.contains(": ")).count(), 21);
+        assert!(output.ends_with("\nNodes searched: 8902\n"));
+
+        let output = execute(&mut uci, &["position startpos", "go perft 0"]);
+        assert_eq!(output, "Nodes searched: 1\n");
+
+        // NOTE: fullmove counter goes beyond the biggest one in FEN here
+        let output = execute(&mut uci, &[
+            "position fen 4k3/8/8/8/8/8/8/4K3 b - - 0 9999 moves e8d8",
+            "go perft 1",
+        ]);
+        assert!(output.ends_with("\nNodes searched: 5\n"));
+    }
+}